@@ -0,0 +1,85 @@
+//! Benchmarks for MAC calculation and header validation.
+//!
+//! Run with `cargo bench`.  The `normalized_string` benchmarks build the complete normalized
+//! string before signing it, as `Mac::new` did before MACs were calculated incrementally, for
+//! comparison with `mac_new`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use hawk::mac::{Mac, MacType, NormalizedString};
+use hawk::{Credentials, DigestAlgorithm, Key, PayloadHash, RequestBuilder, ValidationOptions};
+use std::hint::black_box;
+use std::time::{Duration, SystemTime};
+
+const HASH: [u8; 32] = [42; 32];
+
+fn key(algorithm: DigestAlgorithm) -> Key {
+    Key::new(vec![99u8; algorithm.recommended_key_len()], algorithm).unwrap()
+}
+
+fn bench_mac(c: &mut Criterion) {
+    let ts = SystemTime::now();
+    for algorithm in [DigestAlgorithm::Sha256, DigestAlgorithm::Sha512] {
+        let key = key(algorithm);
+        let mut group = c.benchmark_group(format!("mac/{algorithm}"));
+        group.bench_function("mac_new", |b| {
+            b.iter(|| {
+                Mac::new(
+                    MacType::Header,
+                    &key,
+                    ts,
+                    black_box("j4h3g2"),
+                    "POST",
+                    "example.com",
+                    443,
+                    black_box("/resource/1?b=1&a=2"),
+                    Some(&HASH),
+                    Some("some-app-data"),
+                )
+                .unwrap()
+            })
+        });
+        group.bench_function("normalized_string", |b| {
+            b.iter(|| {
+                let normalized = NormalizedString::new(
+                    MacType::Header,
+                    ts,
+                    black_box("j4h3g2"),
+                    "POST",
+                    "example.com",
+                    443,
+                    black_box("/resource/1?b=1&a=2"),
+                    Some(&HASH),
+                    Some("some-app-data"),
+                )
+                .unwrap();
+                Mac::from(key.sign(normalized.as_bytes()).unwrap())
+            })
+        });
+        group.finish();
+    }
+}
+
+fn bench_validate(c: &mut Criterion) {
+    let credentials = Credentials {
+        id: "me".to_string(),
+        key: key(DigestAlgorithm::Sha256),
+    };
+    let hash = PayloadHash::new(DigestAlgorithm::Sha256, HASH.to_vec()).unwrap();
+    let request = RequestBuilder::new("POST", "example.com", 443, "/resource/1?b=1&a=2")
+        .hash(&hash)
+        .ext("some-app-data")
+        .request();
+    let header = request.make_header(&credentials).unwrap();
+    let options = ValidationOptions::new().ts_skew(Duration::from_secs(3600));
+
+    c.bench_function("validate_header", |b| {
+        b.iter(|| {
+            request
+                .validate_header_with_options(black_box(&header), &credentials.key, &options)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_mac, bench_validate);
+criterion_main!(benches);
@@ -1,7 +1,6 @@
 // Just check that we aren't asked to use an impossible configuration.
 fn main() {
-    assert!(
-        !(cfg!(feature = "use_ring") && cfg!(feature = "use_openssl")),
-        "Cannot configure `hawk` with both `use_ring` and `use_openssl`!"
-    );
+    if cfg!(feature = "use_ring") && cfg!(feature = "use_openssl") {
+        panic!("Cannot configure `hawk` with both `use_ring` and `use_openssl`!");
+    }
 }
@@ -0,0 +1,389 @@
+//! Parsing of RFC 7235 authentication parameters, as used in the `Authorization`,
+//! `Server-Authorization` and `WWW-Authenticate` headers.
+
+use crate::error::{ParseError, ParseErrorKind};
+use std::borrow::Cow;
+
+/// The authentication scheme used by Hawk.
+pub(crate) const SCHEME: &str = "Hawk";
+
+/// Options controlling how Hawk header values are parsed.
+///
+/// By default, unknown attributes are rejected, and header values longer than 4096 bytes (the
+/// limit used by the JS Hawk implementation) are rejected without being parsed.  There is no
+/// limit on the length of individual attributes, beyond that imposed by the header length.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{Header, ParseOptions};
+///
+/// let options = ParseOptions::new().allow_unknown_attributes(true);
+/// let header = Header::parse_authorization(r#"Hawk id="me", future="x""#, &options).unwrap();
+/// assert_eq!(header.id, Some("me".to_string()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub(crate) allow_unknown_attributes: bool,
+    pub(crate) max_header_length: Option<usize>,
+    pub(crate) max_field_length: Option<usize>,
+}
+
+/// The default maximum header length, matching the JS Hawk implementation
+const DEFAULT_MAX_HEADER_LENGTH: usize = 4096;
+
+impl ParseOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        ParseOptions {
+            allow_unknown_attributes: false,
+            max_header_length: Some(DEFAULT_MAX_HEADER_LENGTH),
+            max_field_length: None,
+        }
+    }
+
+    /// Ignore attributes that are not part of the Hawk specification, rather than failing.
+    pub fn allow_unknown_attributes(mut self, allow: bool) -> Self {
+        self.allow_unknown_attributes = allow;
+        self
+    }
+
+    /// Set the maximum length, in bytes, of a header value, or `None` for no limit.
+    pub fn max_header_length(mut self, max: Option<usize>) -> Self {
+        self.max_header_length = max;
+        self
+    }
+
+    /// Set the maximum length, in bytes, of each attribute value, or `None` for no limit.
+    pub fn max_field_length(mut self, max: Option<usize>) -> Self {
+        self.max_field_length = max;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::new()
+    }
+}
+
+/// A single `name=value` parameter.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Param<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) value: Cow<'a, str>,
+    /// Position of the start of the parameter's name
+    pub(crate) position: usize,
+}
+
+/// Check that `s` begins with the Hawk scheme, returning the position of the parameters that
+/// follow it.
+pub(crate) fn strip_scheme(s: &str) -> Result<usize, ParseError> {
+    let bytes = s.as_bytes();
+    let start = skip_ows(bytes, 0);
+    let end = skip_tchars(bytes, start);
+    if end == start {
+        return Err(ParseError::new(ParseErrorKind::ExpectedScheme, start));
+    }
+    let scheme = &s[start..end];
+    if !scheme.eq_ignore_ascii_case(SCHEME) {
+        return Err(ParseError::new(
+            ParseErrorKind::WrongScheme(scheme.to_string()),
+            start,
+        ));
+    }
+    if end < bytes.len() && bytes[end] != b' ' {
+        return Err(ParseError::new(ParseErrorKind::InvalidCharacter, end));
+    }
+    Ok(skip_ows(bytes, end))
+}
+
+/// Check the length of a complete header value against the options.  This should be done before
+/// any other parsing.
+pub(crate) fn check_header_length(s: &str, options: &ParseOptions) -> Result<(), ParseError> {
+    match options.max_header_length {
+        Some(max) if s.len() > max => {
+            Err(ParseError::new(ParseErrorKind::HeaderTooLong { max }, max))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parse a comma-separated list of authentication parameters, beginning at `start`.
+///
+/// Values may be tokens or quoted strings, with backslash escapes.  Empty list elements are
+/// ignored, and duplicate parameter names (compared case-insensitively) are an error, as are
+/// values longer than the maximum field length.
+pub(crate) fn parse_params<'a>(
+    s: &'a str,
+    start: usize,
+    options: &ParseOptions,
+) -> Result<Vec<Param<'a>>, ParseError> {
+    let bytes = s.as_bytes();
+    let mut params: Vec<Param> = vec![];
+    let mut i = start;
+
+    loop {
+        // skip whitespace and empty list elements
+        while i < bytes.len() && (bytes[i] == b',' || is_ows(bytes[i])) {
+            i += 1;
+        }
+        if i == bytes.len() {
+            break;
+        }
+
+        let name_start = i;
+        i = skip_tchars(bytes, i);
+        if i == name_start {
+            return Err(ParseError::new(ParseErrorKind::ExpectedName, i));
+        }
+        let name = &s[name_start..i];
+
+        i = skip_ows(bytes, i);
+        if i == bytes.len() || bytes[i] != b'=' {
+            return Err(ParseError::new(ParseErrorKind::ExpectedEquals, i));
+        }
+        i = skip_ows(bytes, i + 1);
+
+        let value = if i < bytes.len() && bytes[i] == b'"' {
+            let (value, end) = parse_quoted_string(s, i)?;
+            i = end;
+            value
+        } else {
+            let value_start = i;
+            i = skip_tchars(bytes, i);
+            if i == value_start {
+                return Err(ParseError::new(ParseErrorKind::ExpectedValue, i));
+            }
+            Cow::Borrowed(&s[value_start..i])
+        };
+
+        if let Some(max) = options.max_field_length {
+            if value.len() > max {
+                return Err(ParseError::new(
+                    ParseErrorKind::FieldTooLong {
+                        name: name.to_string(),
+                        max,
+                    },
+                    name_start,
+                ));
+            }
+        }
+        if params.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+            return Err(ParseError::new(
+                ParseErrorKind::DuplicateAttribute(name.to_string()),
+                name_start,
+            ));
+        }
+        params.push(Param {
+            name,
+            value,
+            position: name_start,
+        });
+
+        i = skip_ows(bytes, i);
+        if i < bytes.len() && bytes[i] != b',' {
+            return Err(ParseError::new(ParseErrorKind::ExpectedComma, i));
+        }
+    }
+
+    Ok(params)
+}
+
+/// Parse a quoted string starting at the opening quote at `start`, returning the unescaped value
+/// and the position following the closing quote.
+fn parse_quoted_string(s: &str, start: usize) -> Result<(Cow<'_, str>, usize), ParseError> {
+    let bytes = s.as_bytes();
+    // only allocate if the string contains escapes
+    let mut unescaped: Option<String> = None;
+    let mut i = start + 1;
+    let mut chunk_start = i;
+
+    loop {
+        if i == bytes.len() {
+            return Err(ParseError::new(
+                ParseErrorKind::UnterminatedQuotedString,
+                start,
+            ));
+        }
+        match bytes[i] {
+            b'"' => {
+                let value = match unescaped {
+                    Some(mut unescaped) => {
+                        unescaped.push_str(&s[chunk_start..i]);
+                        Cow::Owned(unescaped)
+                    }
+                    None => Cow::Borrowed(&s[chunk_start..i]),
+                };
+                return Ok((value, i + 1));
+            }
+            b'\\' => {
+                let unescaped = unescaped.get_or_insert_with(String::new);
+                unescaped.push_str(&s[chunk_start..i]);
+                i += 1;
+                let c = match s[i..].chars().next() {
+                    Some(c) => c,
+                    None => {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnterminatedQuotedString,
+                            start,
+                        ))
+                    }
+                };
+                if c.is_ascii_control() && c != '\t' {
+                    return Err(ParseError::new(ParseErrorKind::InvalidCharacter, i));
+                }
+                unescaped.push(c);
+                i += c.len_utf8();
+                chunk_start = i;
+            }
+            b if (b < 0x20 && b != b'\t') || b == 0x7f => {
+                return Err(ParseError::new(ParseErrorKind::InvalidCharacter, i));
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+fn is_ows(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+/// Determine whether this is a `tchar` as defined in RFC 7230.
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn skip_ows(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && is_ows(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+fn skip_tchars(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && is_tchar(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Vec<(&str, String)>, ParseError> {
+        Ok(parse_params(s, 0, &ParseOptions::new())?
+            .into_iter()
+            .map(|p| (p.name, p.value.into_owned()))
+            .collect())
+    }
+
+    fn err(s: &str) -> (ParseErrorKind, usize) {
+        let e = parse_params(s, 0, &ParseOptions::new()).unwrap_err();
+        (e.kind().clone(), e.position())
+    }
+
+    #[test]
+    fn test_params() {
+        assert_eq!(parse("").unwrap(), vec![]);
+        assert_eq!(
+            parse(r#"a="b", c=d,e = "f""#).unwrap(),
+            vec![
+                ("a", "b".to_string()),
+                ("c", "d".to_string()),
+                ("e", "f".to_string())
+            ]
+        );
+        assert_eq!(
+            parse(" , a=\"\" ,, \tb=\"x y\" ,").unwrap(),
+            vec![("a", "".to_string()), ("b", "x y".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(
+            parse(r#"a="x\"y\\z\q""#).unwrap(),
+            vec![("a", r#"x"y\zq"#.to_string())]
+        );
+        let params = parse_params(r#"a="no escapes""#, 0, &ParseOptions::new()).unwrap();
+        assert!(matches!(params[0].value, Cow::Borrowed(_)));
+        assert_eq!(parse(r#"a="é\é""#).unwrap(), vec![("a", "éé".to_string())]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(err(r#"a="b" c="d""#), (ParseErrorKind::ExpectedComma, 6));
+        assert_eq!(err(r#"a="b", ="d""#), (ParseErrorKind::ExpectedName, 7));
+        assert_eq!(err(r#"a "b""#), (ParseErrorKind::ExpectedEquals, 2));
+        assert_eq!(err(r#"a="b", c="#), (ParseErrorKind::ExpectedValue, 9));
+        assert_eq!(
+            err(r#"a="b", c="d"#),
+            (ParseErrorKind::UnterminatedQuotedString, 9)
+        );
+        assert_eq!(
+            err(r#"a="b\"#),
+            (ParseErrorKind::UnterminatedQuotedString, 2)
+        );
+        assert_eq!(err("a=\"b\nc\""), (ParseErrorKind::InvalidCharacter, 4));
+        assert_eq!(
+            err(r#"a="b", A="c""#),
+            (ParseErrorKind::DuplicateAttribute("A".to_string()), 7)
+        );
+    }
+
+    #[test]
+    fn test_lengths() {
+        let options = ParseOptions::new().max_field_length(Some(3));
+        assert!(parse_params(r#"a="abc", b=def"#, 0, &options).is_ok());
+        let e = parse_params(r#"a="abc", b=defg"#, 0, &options).unwrap_err();
+        assert_eq!(
+            e.kind(),
+            &ParseErrorKind::FieldTooLong {
+                name: "b".to_string(),
+                max: 3
+            }
+        );
+        assert_eq!(e.position(), 9);
+
+        let long = "a".repeat(5000);
+        assert_eq!(
+            check_header_length(&long, &ParseOptions::new()),
+            Err(ParseError::new(
+                ParseErrorKind::HeaderTooLong { max: 4096 },
+                4096
+            ))
+        );
+        assert!(check_header_length(&long, &ParseOptions::new().max_header_length(None)).is_ok());
+    }
+
+    #[test]
+    fn test_scheme() {
+        assert_eq!(strip_scheme("Hawk id=\"x\""), Ok(5));
+        assert_eq!(strip_scheme("  hawk   id=\"x\""), Ok(9));
+        assert_eq!(strip_scheme("HAWK"), Ok(4));
+        assert_eq!(
+            strip_scheme("Basic abc"),
+            Err(ParseError::new(
+                ParseErrorKind::WrongScheme("Basic".to_string()),
+                0
+            ))
+        );
+        assert_eq!(
+            strip_scheme("Hawkid=\"x\""),
+            Err(ParseError::new(
+                ParseErrorKind::WrongScheme("Hawkid".to_string()),
+                0
+            ))
+        );
+        assert_eq!(
+            strip_scheme(""),
+            Err(ParseError::new(ParseErrorKind::ExpectedScheme, 0))
+        );
+        assert_eq!(
+            strip_scheme("Hawk,id=\"x\""),
+            Err(ParseError::new(ParseErrorKind::InvalidCharacter, 4))
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::b64;
 use crate::error::*;
+use crate::ext::ExtCodec;
 use crate::mac::Mac;
 use base64::Engine;
 use std::borrow::Cow;
@@ -10,6 +11,11 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 /// A Bewit is a piece of data attached to a GET request that functions in place of a Hawk
 /// Authentication header.  It contains an id, a timestamp, a MAC, and an optional `ext` value.
 /// These are available using accessor functions.
+///
+/// With the `serde` feature enabled, bewits serialize as a structure of their fields, with
+/// `exp` given in seconds since the epoch and `mac` base64-encoded.  Deserialization accepts
+/// either that structure or the encoded bewit string.  To serialize a bewit as its encoded
+/// string, use `#[serde(with = "hawk::serde_helpers::bewit_string")]`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Bewit<'a> {
     id: Cow<'a, str>,
@@ -31,6 +37,30 @@ impl<'a> Bewit<'a> {
         }
     }
 
+    pub(crate) fn from_parts(
+        id: Cow<'a, str>,
+        exp: SystemTime,
+        mac: Mac,
+        ext: Option<Cow<'a, str>>,
+    ) -> Bewit<'a> {
+        Bewit {
+            id,
+            exp,
+            mac: Cow::Owned(mac),
+            ext,
+        }
+    }
+
+    /// Convert this bewit into one that owns all of its fields.
+    pub fn into_owned(self) -> Bewit<'static> {
+        Bewit {
+            id: Cow::Owned(self.id.into_owned()),
+            exp: self.exp,
+            mac: Cow::Owned(self.mac.into_owned()),
+            ext: self.ext.map(|ext| Cow::Owned(ext.into_owned())),
+        }
+    }
+
     /// Generate the fully-encoded string for this Bewit
     pub fn to_str(&self) -> String {
         let raw = format!(
@@ -72,6 +102,11 @@ impl<'a> Bewit<'a> {
             None => None,
         }
     }
+
+    /// Decode the Bewit's `ext` field with the given codec, returning `None` if it is not present.
+    pub fn decode_ext<C: ExtCodec>(&self, codec: &C) -> Result<Option<C::Value>> {
+        self.ext().map(|ext| codec.decode(ext)).transpose()
+    }
 }
 
 impl<'a> From<Bewit<'a>> for String {
@@ -98,7 +133,9 @@ impl<'a> FromStr for Bewit<'a> {
 
         let exp = str::from_utf8(parts[1]).map_err(|_| InvalidBewit::Exp)?;
         let exp = u64::from_str(exp).map_err(|_| InvalidBewit::Exp)?;
-        let exp = UNIX_EPOCH + Duration::new(exp, 0);
+        let exp = UNIX_EPOCH
+            .checked_add(Duration::from_secs(exp))
+            .ok_or(InvalidBewit::Exp)?;
 
         let mac = str::from_utf8(parts[2]).map_err(|_| InvalidBewit::Mac)?;
         let mac = Mac::from(
@@ -123,6 +160,71 @@ impl<'a> FromStr for Bewit<'a> {
     }
 }
 
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Bewit")]
+struct BewitFields {
+    id: String,
+    exp: u64,
+    mac: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ext: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum BewitRepr {
+    Encoded(String),
+    Fields(BewitFields),
+}
+
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Bewit<'a> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        BewitFields {
+            id: self.id().to_string(),
+            exp: self
+                .exp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            mac: b64::STANDARD_ENGINE.encode(self.mac()),
+            ext: self.ext().map(|ext| ext.to_string()),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a> serde::Deserialize<'de> for Bewit<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error as _;
+        match BewitRepr::deserialize(deserializer)? {
+            BewitRepr::Encoded(encoded) => Bewit::from_str(&encoded).map_err(D::Error::custom),
+            BewitRepr::Fields(fields) => {
+                let mac = b64::STANDARD_ENGINE
+                    .decode(fields.mac)
+                    .map_err(|_| D::Error::custom(InvalidBewit::Mac))?;
+                let exp = UNIX_EPOCH
+                    .checked_add(Duration::from_secs(fields.exp))
+                    .ok_or_else(|| D::Error::custom(InvalidBewit::Exp))?;
+                Ok(Bewit {
+                    id: Cow::Owned(fields.id),
+                    exp,
+                    mac: Cow::Owned(Mac::from(mac)),
+                    ext: fields.ext.map(Cow::Owned),
+                })
+            }
+        }
+    }
+}
+
 #[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
 mod test {
     use super::*;
@@ -214,6 +316,15 @@ mod test {
         assert!(Bewit::from_str(&bewit).is_err());
     }
 
+    #[test]
+    fn test_from_str_invalid_exp() {
+        let bewit = b64::BEWIT_ENGINE.encode("a\\18446744073709551615\\abc\\".as_bytes());
+        assert!(matches!(
+            Bewit::from_str(&bewit),
+            Err(Error::InvalidBewit(InvalidBewit::Exp))
+        ));
+    }
+
     #[test]
     fn test_from_str_invalid_not_utf8() {
         let a = b'a';
@@ -230,4 +341,55 @@ mod test {
         let bewit = b64::BEWIT_ENGINE.encode([a, slash, one, slash, a, slash, invalid1, invalid2]);
         assert!(Bewit::from_str(&bewit).is_err());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_fields_round_trip() {
+        let bewit = Bewit::from_str(BEWIT_WITH_EXT_STR).unwrap();
+        let json = serde_json::to_value(&bewit).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "me",
+                "exp": 1353832834,
+                "mac": "fiy4e5wBdapDNxHHeA18NrSrU1U3iS66gm0XjTJpYyU=",
+                "ext": "abcd",
+            })
+        );
+        let bewit2: Bewit = serde_json::from_value(json).unwrap();
+        assert_eq!(bewit2, bewit);
+
+        let json = serde_json::json!({
+            "id": "me",
+            "exp": u64::MAX,
+            "mac": "fiy4e5wBdapDNxHHeA18NrSrU1U3iS66gm0XjTJpYyU=",
+        });
+        assert!(serde_json::from_value::<Bewit>(json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_from_encoded() {
+        let bewit: Bewit = serde_json::from_str(&format!("\"{BEWIT_STR}\"")).unwrap();
+        assert_eq!(bewit, Bewit::from_str(BEWIT_STR).unwrap());
+        assert!(serde_json::from_str::<Bewit>("\"!/==\"").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_bewit_string() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Link {
+            #[serde(with = "crate::serde_helpers::bewit_string")]
+            bewit: Bewit<'static>,
+        }
+
+        let link = Link {
+            bewit: Bewit::from_str(BEWIT_STR).unwrap(),
+        };
+        let json = serde_json::to_string(&link).unwrap();
+        assert_eq!(json, format!("{{\"bewit\":\"{BEWIT_STR}\"}}"));
+        let link2: Link = serde_json::from_str(&json).unwrap();
+        assert_eq!(link2.bewit, link.bewit);
+    }
 }
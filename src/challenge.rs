@@ -0,0 +1,329 @@
+use crate::auth_params::{self, Param, ParseOptions};
+use crate::b64;
+use crate::credentials::Key;
+use crate::error::*;
+use crate::header::Header;
+use crate::mac::Mac;
+use base64::Engine;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Representation of a Hawk `WWW-Authenticate` challenge value (the part following "Hawk "),
+/// sent by a server along with a 401 response.
+///
+/// A challenge may contain an `error` message and the server's current time as `ts`, along with
+/// `tsm`, a MAC of that time using the client's key.  A client that receives a challenge with a
+/// valid timestamp can use it to correct for clock skew and retry the request.
+///
+/// Challenges can be derived from strings using the `FromStr` trait or `parse_www_authenticate`,
+/// and formatted into a string using `Display`, which omits the `"Hawk "` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{Challenge, Key, SHA256};
+/// use std::str::FromStr;
+/// use std::time::SystemTime;
+///
+/// let key = Key::new("tok", SHA256).unwrap();
+///
+/// // on the server..
+/// let challenge = Challenge::new(Some("Stale timestamp"))
+///     .unwrap()
+///     .with_timestamp(&key, SystemTime::now())
+///     .unwrap();
+/// let www_authenticate = format!("Hawk {}", challenge);
+///
+/// // ..and on the client
+/// let challenge =
+///     Challenge::parse_www_authenticate(&www_authenticate, &Default::default()).unwrap();
+/// assert_eq!(challenge.error(), Some("Stale timestamp"));
+/// assert!(challenge.validate_timestamp(&key));
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Challenge {
+    ts: Option<SystemTime>,
+    tsm: Option<Mac>,
+    error: Option<String>,
+}
+
+impl Challenge {
+    /// Create a new challenge with the given error message, which is subject to the same
+    /// restrictions as `Header` components.
+    pub fn new<S>(error: Option<S>) -> Result<Challenge>
+    where
+        S: Into<String>,
+    {
+        let error = error.map(Into::into);
+        if let Some(ref error) = error {
+            if !Header::is_valid_component(error) {
+                return Err(Error::HeaderParseError(
+                    "Hawk challenge errors may only contain printable ASCII characters other \
+                     than `\"` and `\\`"
+                        .into(),
+                ));
+            }
+        }
+        Ok(Challenge {
+            ts: None,
+            tsm: None,
+            error,
+        })
+    }
+
+    /// Add the given timestamp to the challenge, along with its MAC using the given key.  This
+    /// is typically the server's current time, allowing the client to correct its clock.
+    pub fn with_timestamp(mut self, key: &Key, ts: SystemTime) -> Result<Challenge> {
+        self.tsm = Some(Mac::timestamp(key, ts)?);
+        self.ts = Some(ts);
+        Ok(self)
+    }
+
+    /// Get the challenge's timestamp.  This is not authenticated unless `validate_timestamp`
+    /// succeeds.
+    pub fn ts(&self) -> Option<SystemTime> {
+        self.ts
+    }
+
+    /// Get the MAC of the challenge's timestamp.
+    pub fn tsm(&self) -> Option<&Mac> {
+        self.tsm.as_ref()
+    }
+
+    /// Get the challenge's error message.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Validate that the challenge's `tsm` is a MAC of its `ts` using the given key.  This
+    /// returns false if either is missing.
+    pub fn validate_timestamp(&self, key: &Key) -> bool {
+        let (ts, tsm) = match (self.ts, self.tsm.as_ref()) {
+            (Some(ts), Some(tsm)) => (ts, tsm),
+            _ => return false,
+        };
+        match Mac::timestamp(key, ts) {
+            Ok(calculated) => &calculated == tsm,
+            Err(_) => false,
+        }
+    }
+
+    /// Parse the attributes of a Hawk challenge, omitting the `"Hawk "` prefix, with the given
+    /// options.  Parsing follows the same rules as `Header::parse`, but only the `ts`, `tsm` and
+    /// `error` attributes are recognized.
+    pub fn parse(s: &str, options: &ParseOptions) -> Result<Challenge> {
+        auth_params::check_header_length(s, options)?;
+        Challenge::from_params(auth_params::parse_params(s, 0, options)?, options)
+    }
+
+    /// Parse a complete `WWW-Authenticate` header value, including the `Hawk` scheme.
+    pub fn parse_www_authenticate(s: &str, options: &ParseOptions) -> Result<Challenge> {
+        auth_params::check_header_length(s, options)?;
+        let start = auth_params::strip_scheme(s)?;
+        Challenge::from_params(auth_params::parse_params(s, start, options)?, options)
+    }
+
+    fn from_params(params: Vec<Param>, options: &ParseOptions) -> Result<Challenge> {
+        let mut challenge = Challenge {
+            ts: None,
+            tsm: None,
+            error: None,
+        };
+
+        for param in params {
+            let invalid = || {
+                ParseError::new(
+                    ParseErrorKind::InvalidValue(param.name.to_string()),
+                    param.position,
+                )
+            };
+            match param.name.to_ascii_lowercase().as_str() {
+                "ts" => {
+                    let epoch = u64::from_str(&param.value).map_err(|_| invalid())?;
+                    let ts = UNIX_EPOCH.checked_add(Duration::from_secs(epoch));
+                    challenge.ts = Some(ts.ok_or_else(invalid)?);
+                }
+                "tsm" => {
+                    let tsm = b64::STANDARD_ENGINE
+                        .decode(param.value.as_bytes())
+                        .map_err(|_| invalid())?;
+                    challenge.tsm = Some(Mac::from(tsm));
+                }
+                "error" => {
+                    if !Header::is_valid_component(&param.value) {
+                        return Err(invalid().into());
+                    }
+                    challenge.error = Some(param.value.into_owned());
+                }
+                _ => {
+                    if !options.allow_unknown_attributes {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnknownAttribute(param.name.to_string()),
+                            param.position,
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+
+        Ok(challenge)
+    }
+}
+
+/// A builder for `Challenge`, created by `HeaderBuilder::challenge`.
+#[derive(Clone, Debug, Default)]
+pub struct ChallengeBuilder<'a> {
+    error: Option<String>,
+    timestamp: Option<(&'a Key, SystemTime)>,
+}
+
+impl<'a> ChallengeBuilder<'a> {
+    /// Set the error message.
+    pub fn error<S: Into<String>>(mut self, error: S) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Include the given timestamp, along with its MAC using the given key.  See
+    /// `Challenge::with_timestamp`.
+    pub fn timestamp(mut self, key: &'a Key, ts: SystemTime) -> Self {
+        self.timestamp = Some((key, ts));
+        self
+    }
+
+    /// Build the challenge, checking the error message as `Challenge::new` does.
+    pub fn build(self) -> Result<Challenge> {
+        let challenge = Challenge::new(self.error)?;
+        match self.timestamp {
+            Some((key, ts)) => challenge.with_timestamp(key, ts),
+            None => Ok(challenge),
+        }
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut sep = "";
+        if let Some(ref ts) = self.ts {
+            write!(
+                f,
+                "{}ts=\"{}\"",
+                sep,
+                ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+            )?;
+            sep = ", ";
+        }
+        if let Some(ref tsm) = self.tsm {
+            write!(f, "{}tsm=\"{}\"", sep, b64::STANDARD_ENGINE.encode(tsm))?;
+            sep = ", ";
+        }
+        if let Some(ref error) = self.error {
+            write!(f, "{sep}error=\"{error}\"")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Challenge {
+    type Err = Error;
+
+    /// Parse the attributes of a Hawk challenge, omitting the `"Hawk "` prefix.  This uses the
+    /// default `ParseOptions`.
+    fn from_str(s: &str) -> Result<Challenge> {
+        Challenge::parse(s, &ParseOptions::new())
+    }
+}
+
+#[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
+mod test {
+    use super::Challenge;
+    use crate::credentials::Key;
+    use crate::error::{Error, ParseErrorKind};
+    use crate::ParseOptions;
+    use std::str::FromStr;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn key() -> Key {
+        Key::new("werxhqb98rpaxn39848xrunpaw3489ruxnpa98w4rxn", crate::SHA256).unwrap()
+    }
+
+    #[test]
+    fn test_format() {
+        let challenge = Challenge::new(Some("Stale timestamp"))
+            .unwrap()
+            .with_timestamp(&key(), UNIX_EPOCH + Duration::from_secs(1353832234))
+            .unwrap();
+        // tsm is the HMAC-SHA256 of "hawk.1.ts\n1353832234\n"
+        assert_eq!(
+            challenge.to_string(),
+            "ts=\"1353832234\", tsm=\"2mw1eh/qXzl0wJZ/E6XvBhRMEJN7L3j8AyMA8eItEb0=\", \
+             error=\"Stale timestamp\""
+        );
+        assert_eq!(Challenge::new::<String>(None).unwrap().to_string(), "");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let challenge = Challenge::new(Some("Stale timestamp"))
+            .unwrap()
+            .with_timestamp(&key(), UNIX_EPOCH + Duration::from_secs(1353832234))
+            .unwrap();
+        let parsed = Challenge::from_str(&challenge.to_string()).unwrap();
+        assert_eq!(parsed, challenge);
+        assert!(parsed.validate_timestamp(&key()));
+        assert!(!parsed.validate_timestamp(&Key::new("other", crate::SHA256).unwrap()));
+    }
+
+    #[test]
+    fn test_parse_www_authenticate() {
+        let options = ParseOptions::new();
+        let challenge =
+            Challenge::parse_www_authenticate("Hawk error=\"Unknown credentials\"", &options)
+                .unwrap();
+        assert_eq!(challenge.error(), Some("Unknown credentials"));
+        assert_eq!(challenge.ts(), None);
+        assert!(!challenge.validate_timestamp(&key()));
+
+        let challenge = Challenge::parse_www_authenticate("Hawk", &options).unwrap();
+        assert_eq!(challenge.error(), None);
+
+        // a tsm that does not match the ts is rejected
+        let challenge = Challenge::parse_www_authenticate(
+            "Hawk ts=\"1353832235\", tsm=\"2mw1eh/qXzl0wJZ/E6XvBhRMEJN7L3j8AyMA8eItEb0=\"",
+            &options,
+        )
+        .unwrap();
+        assert!(!challenge.validate_timestamp(&key()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // header attributes are not valid in challenges
+        match Challenge::from_str("ts=\"1353832234\", id=\"me\"") {
+            Err(Error::Parse(e)) => {
+                assert_eq!(e.kind(), &ParseErrorKind::UnknownAttribute("id".into()));
+                assert_eq!(e.position(), 17);
+            }
+            r => panic!("unexpected result {r:?}"),
+        }
+        let options = ParseOptions::new().allow_unknown_attributes(true);
+        assert!(Challenge::parse("id=\"me\"", &options).is_ok());
+
+        assert!(Challenge::from_str("ts=\"soon\"").is_err());
+        match Challenge::parse_www_authenticate(
+            "Hawk error=\"Stale timestamp\", ts=\"18446744073709551615\"",
+            &ParseOptions::new(),
+        ) {
+            Err(Error::Parse(e)) => {
+                assert_eq!(e.kind(), &ParseErrorKind::InvalidValue("ts".into()));
+                assert_eq!(e.position(), 30);
+            }
+            r => panic!("unexpected result {r:?}"),
+        }
+        assert!(Challenge::from_str("tsm=\"!!\"").is_err());
+        assert!(Challenge::from_str("error=\"a\\\"b\"").is_err());
+        assert!(Challenge::new(Some("a\"b")).is_err());
+    }
+}
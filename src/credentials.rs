@@ -1,5 +1,22 @@
+use crate::b64;
 use crate::crypto::{self, HmacKey};
+use crate::error::*;
+use crate::key_policy::KeyPolicy;
+use crate::secret::SecretBytes;
+use base64::Engine;
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::{self, FromStr};
+use std::sync::Arc;
+use zeroize::Zeroizing;
 
+/// The digest algorithm used for MACs and payload hashes.
+///
+/// Algorithms are formatted and parsed using the names used by the reference Hawk
+/// implementation (`"sha256"`, `"sha384"` and `"sha512"`), which makes it convenient to store
+/// them alongside credentials in configuration.  With the `serde` feature enabled, the same
+/// names are used for serialization.
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
 #[non_exhaustive]
 pub enum DigestAlgorithm {
@@ -8,34 +25,504 @@ pub enum DigestAlgorithm {
     Sha512,
 }
 
+impl DigestAlgorithm {
+    /// All digest algorithms known to this crate, whether or not the active
+    /// `Cryptographer` supports them.
+    pub const ALL: &'static [DigestAlgorithm] = &[
+        DigestAlgorithm::Sha256,
+        DigestAlgorithm::Sha384,
+        DigestAlgorithm::Sha512,
+    ];
+
+    /// Get the canonical Hawk name for this algorithm, e.g., `"sha256"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Sha384 => "sha384",
+            DigestAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Get the length of this algorithm's digest output, in bytes.
+    pub fn output_len(self) -> usize {
+        match self {
+            DigestAlgorithm::Sha256 => 32,
+            DigestAlgorithm::Sha384 => 48,
+            DigestAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Get the recommended length of keys for this algorithm, in bytes.  This is the length
+    /// of the digest output, as recommended by RFC 2104.
+    pub fn recommended_key_len(self) -> usize {
+        self.output_len()
+    }
+
+    /// Determine whether the active `Cryptographer` supports this algorithm.
+    ///
+    /// This allows configuration to be validated early, rather than failing later in
+    /// `Key::new` or `PayloadHasher::new`.
+    pub fn is_supported(self) -> bool {
+        crypto::supports_digest(self)
+    }
+
+    /// Get the algorithms supported by the active `Cryptographer`, in the order given by
+    /// `DigestAlgorithm::ALL`.
+    pub fn supported() -> Vec<DigestAlgorithm> {
+        DigestAlgorithm::ALL
+            .iter()
+            .copied()
+            .filter(|a| a.is_supported())
+            .collect()
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<DigestAlgorithm> {
+        DigestAlgorithm::ALL
+            .iter()
+            .copied()
+            .find(|a| a.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| Error::UnknownDigestAlgorithm(s.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DigestAlgorithm {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DigestAlgorithm {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        DigestAlgorithm::from_str(&name).map_err(serde::de::Error::custom)
+    }
+}
+
 /// Hawk key.
 ///
 /// While any sequence of bytes can be specified as a key, note that each digest algorithm has
 /// a suggested key length, and that passwords should *not* be used as keys.  Keys of incorrect
 /// length are handled according to the digest's implementation.
-pub struct Key(Box<dyn HmacKey>);
+///
+/// The key material itself is held by the configured `Cryptographer`, and is never revealed by
+/// this type; in particular, its `Debug` implementation shows only the digest algorithm.
+///
+/// Keys are cheap to clone: clones share the underlying key context.
+#[derive(Clone)]
+pub struct Key {
+    key: Arc<dyn HmacKey>,
+    algorithm: DigestAlgorithm,
+}
 
 impl Key {
-    pub fn new<B>(key: B, algorithm: DigestAlgorithm) -> crate::Result<Key>
+    /// Create a new key from the given key material.
+    ///
+    /// The material is passed to the `Cryptographer` and not retained by this type.  Consider
+    /// using [`SecretBytes`] to hold the material, so that it is cleared once it is no longer
+    /// needed.
+    pub fn new<B>(key: B, algorithm: DigestAlgorithm) -> Result<Key>
     where
         B: AsRef<[u8]>,
     {
-        Ok(Key(crypto::new_key(algorithm, key.as_ref())?))
+        Ok(Key {
+            key: crypto::new_key(algorithm, key.as_ref())?.into(),
+            algorithm,
+        })
+    }
+
+    /// Create a new key from an existing `HmacKey` implementation, such as a key held in a KMS
+    /// or HSM.  The algorithm must be that used by the `HmacKey`.
+    ///
+    /// The key is used once to sign an empty message, and an error is returned if the MAC is not
+    /// the length produced by `algorithm`.
+    pub fn from_hmac_key(key: Box<dyn HmacKey>, algorithm: DigestAlgorithm) -> Result<Key> {
+        let mut output = [0u8; crypto::MAX_MAC_LEN];
+        let len = key.sign_parts(&[], &mut output)?;
+        if len != algorithm.output_len() {
+            return Err(crypto::CryptoError::Other(anyhow::anyhow!(
+                "HmacKey produced a {len}-byte MAC, but {algorithm} MACs are {} bytes",
+                algorithm.output_len()
+            ))
+            .into());
+        }
+        Ok(Key {
+            key: key.into(),
+            algorithm,
+        })
     }
 
-    pub fn sign(&self, data: &[u8]) -> crate::Result<Vec<u8>> {
-        Ok(self.0.sign(data)?)
+    /// Create a new key, first checking the key material against the default `KeyPolicy`.
+    ///
+    /// Use this constructor where keys are under your control and should be strong.  `Key::new`
+    /// accepts any key, which may be necessary to interoperate with existing credentials.
+    pub fn new_strict<B>(key: B, algorithm: DigestAlgorithm) -> Result<Key>
+    where
+        B: AsRef<[u8]>,
+    {
+        Key::new_with_policy(key, algorithm, &KeyPolicy::default())
+    }
+
+    /// Create a new key, first checking the key material against the given policy.
+    pub fn new_with_policy<B>(key: B, algorithm: DigestAlgorithm, policy: &KeyPolicy) -> Result<Key>
+    where
+        B: AsRef<[u8]>,
+    {
+        policy.check(key.as_ref(), algorithm)?;
+        Key::new(key, algorithm)
+    }
+
+    /// Generate a new random key of the recommended length for the algorithm, using the
+    /// configured `Cryptographer`.
+    ///
+    /// The generated key material is returned alongside the key, so that it can be persisted or
+    /// shared with the other party; it cannot be recovered from the `Key` later.
+    pub fn generate(algorithm: DigestAlgorithm) -> Result<(Key, SecretBytes)> {
+        let material = SecretBytes::generate(algorithm.recommended_key_len())?;
+        Ok((Key::new(&material, algorithm)?, material))
+    }
+
+    /// Derive a new key from a secret using HKDF (RFC 5869), with the given salt and a label
+    /// identifying the context in which the key is used.
+    ///
+    /// The derived key has the recommended length for the algorithm.  HKDF is suitable for
+    /// secrets that already have high entropy; use `Key::from_pbkdf2` for passphrases.
+    pub fn from_hkdf<B>(
+        secret: B,
+        salt: &[u8],
+        label: &str,
+        algorithm: DigestAlgorithm,
+    ) -> Result<Key>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut material = SecretBytes::new(vec![0u8; algorithm.recommended_key_len()]);
+        crypto::hkdf(
+            algorithm,
+            salt,
+            secret.as_ref(),
+            label.as_bytes(),
+            material.expose_secret_mut(),
+        )?;
+        Key::new(&material, algorithm)
+    }
+
+    /// Derive a new key from a passphrase using PBKDF2 (RFC 8018) with the given salt and
+    /// number of iterations.
+    ///
+    /// The derived key has the recommended length for the algorithm.  The salt should be
+    /// unique to the credentials, and the number of iterations as high as can be tolerated.
+    pub fn from_pbkdf2<B>(
+        password: B,
+        salt: &[u8],
+        iterations: NonZeroU32,
+        algorithm: DigestAlgorithm,
+    ) -> Result<Key>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut material = SecretBytes::new(vec![0u8; algorithm.recommended_key_len()]);
+        crypto::pbkdf2(
+            algorithm,
+            password.as_ref(),
+            salt,
+            iterations,
+            material.expose_secret_mut(),
+        )?;
+        Key::new(&material, algorithm)
+    }
+
+    /// Create a new key from key material in the given encoding.
+    pub fn from_encoded(
+        encoded: &str,
+        encoding: KeyEncoding,
+        algorithm: DigestAlgorithm,
+    ) -> Result<Key> {
+        Key::new(encoding.decode(encoded)?, algorithm)
+    }
+
+    /// Create a new key from standard, padded base64-encoded key material.
+    pub fn from_base64(encoded: &str, algorithm: DigestAlgorithm) -> Result<Key> {
+        Key::from_encoded(encoded, KeyEncoding::Base64, algorithm)
+    }
+
+    /// Create a new key from URL-safe, unpadded base64-encoded key material.
+    pub fn from_base64url(encoded: &str, algorithm: DigestAlgorithm) -> Result<Key> {
+        Key::from_encoded(encoded, KeyEncoding::Base64Url, algorithm)
+    }
+
+    /// Create a new key from hex-encoded key material.
+    pub fn from_hex(encoded: &str, algorithm: DigestAlgorithm) -> Result<Key> {
+        Key::from_encoded(encoded, KeyEncoding::Hex, algorithm)
+    }
+
+    /// Get the digest algorithm for this key.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.key.sign(data)?)
+    }
+
+    /// Sign the concatenation of `parts`, writing the MAC into `output` and returning the
+    /// portion of `output` that it occupies.
+    pub(crate) fn sign_parts<'o>(
+        &self,
+        parts: &[&[u8]],
+        output: &'o mut [u8; crypto::MAX_MAC_LEN],
+    ) -> Result<&'o [u8]> {
+        let len = self.key.sign_parts(parts, output)?;
+        Ok(&output[..len])
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Key")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
     }
 }
 
 /// Hawk credentials: an ID and a key associated with that ID.  The digest algorithm
 /// must be agreed between the server and the client, and the length of the key is
 /// specific to that algorithm.
+///
+/// The `Debug` implementation does not reveal the key material.
+#[derive(Clone, Debug)]
 pub struct Credentials {
     pub id: String,
     pub key: Key,
 }
 
+impl Credentials {
+    /// Create new credentials from an id and key material.
+    ///
+    /// The key material is cleared once the key has been created.
+    pub fn new<S, B>(id: S, key: B, algorithm: DigestAlgorithm) -> Result<Credentials>
+    where
+        S: Into<String>,
+        B: Into<SecretBytes>,
+    {
+        let key: SecretBytes = key.into();
+        Ok(Credentials {
+            id: id.into(),
+            key: Key::new(&key, algorithm)?,
+        })
+    }
+
+    /// Generate new credentials with a random key; see `Key::generate`.
+    pub fn generate<S>(id: S, algorithm: DigestAlgorithm) -> Result<(Credentials, SecretBytes)>
+    where
+        S: Into<String>,
+    {
+        let (key, material) = Key::generate(algorithm)?;
+        Ok((Credentials { id: id.into(), key }, material))
+    }
+}
+
+/// The encoding of key material in a textual representation such as a configuration file.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum KeyEncoding {
+    /// The key is used as-is, as in the reference Hawk implementation.  It must be valid UTF-8.
+    #[default]
+    Raw,
+    /// Standard base64, with padding.
+    Base64,
+    /// URL-safe base64, without padding.
+    Base64Url,
+    /// Hexadecimal, in either case.
+    Hex,
+}
+
+impl KeyEncoding {
+    /// Encode key material in this encoding.
+    ///
+    /// The result is overwritten with zeroes when it is dropped.
+    pub fn encode(self, key: &[u8]) -> Result<Zeroizing<String>> {
+        let encoded = match self {
+            KeyEncoding::Raw => str::from_utf8(key)
+                .map_err(|_| Error::InvalidKeyEncoding("raw key is not valid UTF-8".into()))?
+                .to_owned(),
+            KeyEncoding::Base64 => b64::STANDARD_ENGINE.encode(key),
+            KeyEncoding::Base64Url => b64::BEWIT_ENGINE.encode(key),
+            KeyEncoding::Hex => {
+                const DIGITS: &[u8; 16] = b"0123456789abcdef";
+                let mut encoded = String::with_capacity(key.len() * 2);
+                for b in key {
+                    encoded.push(char::from(DIGITS[usize::from(b >> 4)]));
+                    encoded.push(char::from(DIGITS[usize::from(b & 0xf)]));
+                }
+                encoded
+            }
+        };
+        Ok(Zeroizing::new(encoded))
+    }
+
+    /// Decode key material from this encoding.
+    pub fn decode(self, encoded: &str) -> Result<SecretBytes> {
+        // decode into a buffer that is large enough to never be reallocated, and which is cleared
+        // if decoding fails part-way through
+        let mut decoded = Zeroizing::new(Vec::new());
+        match self {
+            KeyEncoding::Raw => decoded.extend_from_slice(encoded.as_bytes()),
+            KeyEncoding::Base64 | KeyEncoding::Base64Url => {
+                let engine = if self == KeyEncoding::Base64 {
+                    &b64::STANDARD_ENGINE
+                } else {
+                    &b64::BEWIT_ENGINE
+                };
+                decoded.reserve_exact(base64::decoded_len_estimate(encoded.len()));
+                engine
+                    .decode_vec(encoded, &mut decoded)
+                    .map_err(|e| Error::InvalidKeyEncoding(e.to_string()))?;
+            }
+            KeyEncoding::Hex => {
+                let pairs = encoded.as_bytes().chunks_exact(2);
+                if !pairs.remainder().is_empty() {
+                    return Err(Error::InvalidKeyEncoding(
+                        "hex key has an odd number of digits".into(),
+                    ));
+                }
+                decoded.reserve_exact(pairs.len());
+                for digits in pairs {
+                    let byte = char::from(digits[0])
+                        .to_digit(16)
+                        .zip(char::from(digits[1]).to_digit(16))
+                        .map(|(hi, lo)| (hi * 16 + lo) as u8)
+                        .ok_or_else(|| Error::InvalidKeyEncoding("invalid hex digit".into()))?;
+                    decoded.push(byte);
+                }
+            }
+        }
+        Ok(SecretBytes::from(std::mem::take(&mut *decoded)))
+    }
+}
+
+/// A description of Hawk credentials, suitable for storing in configuration.
+///
+/// Unlike `Credentials`, this retains the key material, along with the encoding in which it
+/// should be represented.  With the `serde` feature enabled, it serializes as a structure with
+/// fields `id`, `algorithm`, `key` (encoded with `encoding`) and `encoding`, which defaults to
+/// `raw` if omitted.
+///
+/// The key material is cleared when the descriptor is dropped, and is not revealed by the
+/// `Debug` implementation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CredentialsDescriptor {
+    pub id: String,
+    pub algorithm: DigestAlgorithm,
+    pub key: SecretBytes,
+    pub encoding: KeyEncoding,
+}
+
+impl CredentialsDescriptor {
+    /// Create a new descriptor, using the `Raw` encoding.
+    pub fn new<S, B>(id: S, key: B, algorithm: DigestAlgorithm) -> Self
+    where
+        S: Into<String>,
+        B: Into<SecretBytes>,
+    {
+        CredentialsDescriptor {
+            id: id.into(),
+            algorithm,
+            key: key.into(),
+            encoding: KeyEncoding::Raw,
+        }
+    }
+
+    /// Set the encoding used to represent the key material.
+    pub fn encoding(mut self, encoding: KeyEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Build `Credentials` from this descriptor.
+    pub fn to_credentials(&self) -> Result<Credentials> {
+        Ok(Credentials {
+            id: self.id.clone(),
+            key: Key::new(&self.key, self.algorithm)?,
+        })
+    }
+}
+
+impl TryFrom<&CredentialsDescriptor> for Credentials {
+    type Error = Error;
+    fn try_from(descriptor: &CredentialsDescriptor) -> Result<Credentials> {
+        descriptor.to_credentials()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Credentials")]
+struct CredentialsDescriptorRepr {
+    id: String,
+    algorithm: DigestAlgorithm,
+    key: String,
+    #[serde(default)]
+    encoding: KeyEncoding,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CredentialsDescriptor {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let mut repr = CredentialsDescriptorRepr {
+            id: self.id.clone(),
+            algorithm: self.algorithm,
+            key: std::mem::take(
+                &mut *self
+                    .encoding
+                    .encode(self.key.expose_secret())
+                    .map_err(serde::ser::Error::custom)?,
+            ),
+            encoding: self.encoding,
+        };
+        let result = repr.serialize(serializer);
+        zeroize::Zeroize::zeroize(&mut repr.key);
+        result
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CredentialsDescriptor {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let mut repr = CredentialsDescriptorRepr::deserialize(deserializer)?;
+        let key = repr.encoding.decode(&repr.key);
+        zeroize::Zeroize::zeroize(&mut repr.key);
+        Ok(CredentialsDescriptor {
+            key: key.map_err(serde::de::Error::custom)?,
+            id: repr.id,
+            algorithm: repr.algorithm,
+            encoding: repr.encoding,
+        })
+    }
+}
+
 #[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
 mod test {
     use super::*;
@@ -53,4 +540,300 @@ mod test {
         let key = vec![0u8; 99];
         Key::new(key, DigestAlgorithm::Sha256).unwrap();
     }
+
+    #[test]
+    fn test_digest_algorithm_round_trip() {
+        for algorithm in DigestAlgorithm::ALL {
+            let name = algorithm.to_string();
+            assert_eq!(DigestAlgorithm::from_str(&name).unwrap(), *algorithm);
+        }
+        assert_eq!(DigestAlgorithm::Sha384.to_string(), "sha384");
+    }
+
+    #[test]
+    fn test_digest_algorithm_from_str() {
+        assert_eq!(
+            DigestAlgorithm::from_str("SHA256").unwrap(),
+            DigestAlgorithm::Sha256
+        );
+        assert!(matches!(
+            DigestAlgorithm::from_str("sha1"),
+            Err(Error::UnknownDigestAlgorithm(ref s)) if s == "sha1"
+        ));
+        assert!(DigestAlgorithm::from_str("sha-256").is_err());
+    }
+
+    #[test]
+    fn test_digest_algorithm_supported() {
+        // both built-in cryptographers support every algorithm
+        assert_eq!(DigestAlgorithm::supported(), DigestAlgorithm::ALL);
+        assert!(DigestAlgorithm::Sha512.is_supported());
+    }
+
+    #[test]
+    fn test_key_algorithm() {
+        let key = Key::new(vec![77u8; 48], DigestAlgorithm::Sha384).unwrap();
+        assert_eq!(key.algorithm(), DigestAlgorithm::Sha384);
+    }
+
+    #[test]
+    fn test_new_strict() {
+        assert!(matches!(
+            Key::new_strict("tok", DigestAlgorithm::Sha256),
+            Err(Error::WeakKey(WeakKey::TooShort { len: 3, min: 32 }))
+        ));
+        assert!(Key::new_strict(vec![77u8; 32], DigestAlgorithm::Sha256).is_err());
+        let (_, material) = Key::generate(DigestAlgorithm::Sha256).unwrap();
+        Key::new_strict(&material, DigestAlgorithm::Sha256).unwrap();
+    }
+
+    /// An `HmacKey` whose MAC is the data reversed, padded or truncated to the given length.
+    struct FixedHmacKey(usize);
+
+    impl HmacKey for FixedHmacKey {
+        fn sign(&self, data: &[u8]) -> std::result::Result<Vec<u8>, crypto::CryptoError> {
+            let mut mac: Vec<u8> = data.iter().rev().copied().collect();
+            mac.resize(self.0, 0);
+            Ok(mac)
+        }
+    }
+
+    #[test]
+    fn test_from_hmac_key() {
+        let key = Key::from_hmac_key(Box::new(FixedHmacKey(48)), DigestAlgorithm::Sha384).unwrap();
+        assert_eq!(key.algorithm(), DigestAlgorithm::Sha384);
+        let mac = key.sign(b"abc").unwrap();
+        assert_eq!(mac.len(), 48);
+        assert_eq!(&mac[..3], b"cba");
+
+        // the default `sign_parts` concatenates the parts
+        let mut output = [0u8; crypto::MAX_MAC_LEN];
+        assert_eq!(
+            key.sign_parts(&[b"a", b"bc"], &mut output).unwrap(),
+            &mac[..]
+        );
+    }
+
+    #[test]
+    fn test_from_hmac_key_wrong_length() {
+        assert!(matches!(
+            Key::from_hmac_key(Box::new(FixedHmacKey(32)), DigestAlgorithm::Sha384),
+            Err(Error::Crypto(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate() {
+        for algorithm in DigestAlgorithm::ALL {
+            let (key, material) = Key::generate(*algorithm).unwrap();
+            assert_eq!(material.len(), algorithm.output_len());
+            assert_eq!(key.algorithm(), *algorithm);
+            let restored = Key::new(&material, *algorithm).unwrap();
+            assert_eq!(key.sign(b"data").unwrap(), restored.sign(b"data").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_generate_unique() {
+        let (_, material1) = Key::generate(DigestAlgorithm::Sha256).unwrap();
+        let (_, material2) = Key::generate(DigestAlgorithm::Sha256).unwrap();
+        assert_ne!(material1, material2);
+    }
+
+    #[test]
+    fn test_generate_credentials() {
+        let (credentials, material) = Credentials::generate("me", DigestAlgorithm::Sha384).unwrap();
+        assert_eq!(credentials.id, "me");
+        let encoded = KeyEncoding::Base64
+            .encode(material.expose_secret())
+            .unwrap();
+        let restored = Key::from_base64(&encoded, DigestAlgorithm::Sha384).unwrap();
+        assert_eq!(
+            credentials.key.sign(b"data").unwrap(),
+            restored.sign(b"data").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_hkdf() {
+        let key = Key::from_hkdf("secret", b"salt", "hawk", DigestAlgorithm::Sha256).unwrap();
+        let mut material = [0u8; 32];
+        crypto::hkdf(
+            DigestAlgorithm::Sha256,
+            b"salt",
+            b"secret",
+            b"hawk",
+            &mut material,
+        )
+        .unwrap();
+        let expected = Key::new(material, DigestAlgorithm::Sha256).unwrap();
+        assert_eq!(key.sign(b"data").unwrap(), expected.sign(b"data").unwrap());
+
+        // a different label gives a different key
+        let other = Key::from_hkdf("secret", b"salt", "other", DigestAlgorithm::Sha256).unwrap();
+        assert_ne!(key.sign(b"data").unwrap(), other.sign(b"data").unwrap());
+    }
+
+    #[test]
+    fn test_from_pbkdf2() {
+        let iterations = NonZeroU32::new(2).unwrap();
+        for algorithm in DigestAlgorithm::ALL {
+            let key = Key::from_pbkdf2("passphrase", b"salt", iterations, *algorithm).unwrap();
+            let mut material = vec![0u8; algorithm.recommended_key_len()];
+            crypto::pbkdf2(
+                *algorithm,
+                b"passphrase",
+                b"salt",
+                iterations,
+                &mut material,
+            )
+            .unwrap();
+            let expected = Key::new(material, *algorithm).unwrap();
+            assert_eq!(key.algorithm(), *algorithm);
+            assert_eq!(key.sign(b"data").unwrap(), expected.sign(b"data").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_from_encoded() {
+        let expected = Key::new([0u8, 1, 2, 0xfb, 0xfe, 0xff], DigestAlgorithm::Sha256)
+            .unwrap()
+            .sign(b"data")
+            .unwrap();
+        for key in [
+            Key::from_base64("AAEC+/7/", DigestAlgorithm::Sha256).unwrap(),
+            Key::from_base64url("AAEC-_7_", DigestAlgorithm::Sha256).unwrap(),
+            Key::from_hex("000102FBFEFF", DigestAlgorithm::Sha256).unwrap(),
+        ] {
+            assert_eq!(key.sign(b"data").unwrap(), expected);
+        }
+        assert!(Key::from_hex("0g", DigestAlgorithm::Sha256).is_err());
+        assert!(Key::from_base64("AAEC-_7_", DigestAlgorithm::Sha256).is_err());
+    }
+
+    #[test]
+    fn test_clone_signs_identically() {
+        let credentials = Credentials::new("me", vec![77u8; 32], DigestAlgorithm::Sha256).unwrap();
+        let cloned = credentials.clone();
+        assert_eq!(cloned.id, "me");
+        assert_eq!(cloned.key.algorithm(), DigestAlgorithm::Sha256);
+        assert_eq!(
+            cloned.key.sign(b"data").unwrap(),
+            credentials.key.sign(b"data").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_clone_across_threads() {
+        let key = Key::new(vec![77u8; 64], DigestAlgorithm::Sha512).unwrap();
+        let expected = key.sign(b"data").unwrap();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let key = key.clone();
+                std::thread::spawn(move || key.sign(b"data").unwrap())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_debug_redacted() {
+        let credentials = Credentials::new("me", "hunter2", DigestAlgorithm::Sha256).unwrap();
+        let debug = format!("{credentials:?}");
+        assert_eq!(
+            debug,
+            "Credentials { id: \"me\", key: Key { algorithm: Sha256, .. } }"
+        );
+
+        let descriptor = CredentialsDescriptor::new("me", "hunter2", DigestAlgorithm::Sha256);
+        let debug = format!("{descriptor:?}");
+        assert!(!debug.contains("hunter2"), "{debug}");
+        assert!(!debug.contains("104, 117"), "{debug}");
+    }
+
+    #[test]
+    fn test_key_encoding_round_trip() {
+        let key = vec![0u8, 1, 2, 0xfb, 0xfe, 0xff];
+        for encoding in [
+            KeyEncoding::Base64,
+            KeyEncoding::Base64Url,
+            KeyEncoding::Hex,
+        ] {
+            let encoded = encoding.encode(&key).unwrap();
+            assert_eq!(encoding.decode(&encoded).unwrap().expose_secret(), key);
+        }
+        assert_eq!(*KeyEncoding::Hex.encode(&key).unwrap(), "000102fbfeff");
+        assert_eq!(*KeyEncoding::Base64.encode(&key).unwrap(), "AAEC+/7/");
+        assert_eq!(*KeyEncoding::Base64Url.encode(&key).unwrap(), "AAEC-_7_");
+        assert!(KeyEncoding::Raw.encode(&key).is_err());
+        assert_eq!(*KeyEncoding::Raw.encode(b"tok").unwrap(), "tok");
+    }
+
+    #[test]
+    fn test_key_encoding_invalid() {
+        assert!(KeyEncoding::Hex.decode("abc").is_err());
+        assert!(KeyEncoding::Hex.decode("zz").is_err());
+        assert!(KeyEncoding::Hex.decode("+f").is_err());
+        assert_eq!(
+            KeyEncoding::Hex.decode("0aFF").unwrap(),
+            SecretBytes::from(vec![10, 255])
+        );
+        assert!(KeyEncoding::Hex.decode("é0").is_err());
+        assert!(KeyEncoding::Base64.decode("!!").is_err());
+    }
+
+    #[test]
+    fn test_descriptor_to_credentials() {
+        let descriptor = CredentialsDescriptor::new("me", "tok", DigestAlgorithm::Sha256);
+        let credentials = Credentials::try_from(&descriptor).unwrap();
+        let expected = Key::new("tok", DigestAlgorithm::Sha256).unwrap();
+        assert_eq!(credentials.id, "me");
+        assert_eq!(
+            credentials.key.sign(b"data").unwrap(),
+            expected.sign(b"data").unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_descriptor_serde() {
+        let descriptor = CredentialsDescriptor::new("me", vec![1u8, 2, 3], DigestAlgorithm::Sha384)
+            .encoding(KeyEncoding::Hex);
+        let json = serde_json::to_value(&descriptor).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "me",
+                "algorithm": "sha384",
+                "key": "010203",
+                "encoding": "hex",
+            })
+        );
+        let descriptor2: CredentialsDescriptor = serde_json::from_value(json).unwrap();
+        assert!(descriptor2 == descriptor);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_descriptor_serde_default_encoding() {
+        let descriptor: CredentialsDescriptor =
+            serde_json::from_str(r#"{"id": "me", "algorithm": "sha256", "key": "tok"}"#).unwrap();
+        assert!(descriptor == CredentialsDescriptor::new("me", "tok", DigestAlgorithm::Sha256));
+        assert!(serde_json::from_str::<CredentialsDescriptor>(
+            r#"{"id": "me", "algorithm": "sha256", "key": "tok", "encoding": "base64"}"#
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_digest_algorithm_serde() {
+        let json = serde_json::to_string(&DigestAlgorithm::Sha512).unwrap();
+        assert_eq!(json, "\"sha512\"");
+        let algorithm: DigestAlgorithm = serde_json::from_str("\"sha256\"").unwrap();
+        assert_eq!(algorithm, DigestAlgorithm::Sha256);
+        assert!(serde_json::from_str::<DigestAlgorithm>("\"md5\"").is_err());
+    }
 }
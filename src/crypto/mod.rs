@@ -9,6 +9,9 @@
 //! [`Cryptographer`] and using the [`set_cryptographer`] or
 //! [`set_boxed_cryptographer`] functions.
 use crate::DigestAlgorithm;
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::pin::Pin;
 
 pub(crate) mod holder;
 pub(crate) use holder::get_crypographer;
@@ -28,6 +31,11 @@ pub enum CryptoError {
     #[error("Digest algorithm {0:?} is unsupported by this Cryptographer")]
     UnsupportedDigest(DigestAlgorithm),
 
+    /// The configured cryptographer does not implement the named operation. This
+    /// should only happen for custom `Cryptographer` implementations
+    #[error("Operation {0} is unsupported by this Cryptographer")]
+    UnsupportedOperation(&'static str),
+
     /// The configured cryptographer implementation failed to perform an
     /// operation in some way.
     #[error("{0}")]
@@ -40,6 +48,10 @@ pub enum CryptoError {
 /// then you do not have to worry about this.
 pub trait Cryptographer: Send + Sync + 'static {
     fn rand_bytes(&self, output: &mut [u8]) -> Result<(), CryptoError>;
+
+    /// Create a new HMAC key.  The caller clears `key` after this call, so implementations
+    /// should avoid making copies of it other than in the returned key, and should clear any
+    /// such copies when the key is dropped, if possible.
     fn new_key(
         &self,
         algorithm: DigestAlgorithm,
@@ -47,11 +59,81 @@ pub trait Cryptographer: Send + Sync + 'static {
     ) -> Result<Box<dyn HmacKey>, CryptoError>;
     fn new_hasher(&self, algo: DigestAlgorithm) -> Result<Box<dyn Hasher>, CryptoError>;
     fn constant_time_compare(&self, a: &[u8], b: &[u8]) -> bool;
+
+    /// Derive key material into `output` using HKDF (RFC 5869), extracting from `ikm` with
+    /// `salt` and expanding with `info`.
+    ///
+    /// The default implementation returns `CryptoError::UnsupportedOperation`.
+    fn hkdf(
+        &self,
+        algorithm: DigestAlgorithm,
+        salt: &[u8],
+        ikm: &[u8],
+        info: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        let _ = (algorithm, salt, ikm, info, output);
+        Err(CryptoError::UnsupportedOperation("hkdf"))
+    }
+
+    /// Derive key material into `output` from `password` using PBKDF2 (RFC 8018) with HMAC
+    /// over the given digest algorithm.
+    ///
+    /// The default implementation returns `CryptoError::UnsupportedOperation`.
+    fn pbkdf2(
+        &self,
+        algorithm: DigestAlgorithm,
+        password: &[u8],
+        salt: &[u8],
+        iterations: NonZeroU32,
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        let _ = (algorithm, password, salt, iterations, output);
+        Err(CryptoError::UnsupportedOperation("pbkdf2"))
+    }
+
+    /// Determine whether this cryptographer supports the given digest algorithm.
+    ///
+    /// The default implementation attempts to create a hasher for the algorithm.
+    fn supports_digest(&self, algorithm: DigestAlgorithm) -> bool {
+        self.new_hasher(algorithm).is_ok()
+    }
 }
 
 /// Type-erased hmac key type.
+///
+/// This is usually created by a `Cryptographer`, but can also be implemented directly for keys
+/// that are held elsewhere, such as in an HSM, and used with `Key::from_hmac_key`.
 pub trait HmacKey: Send + Sync + 'static {
     fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError>;
+
+    /// Sign the concatenation of `parts`, writing the MAC into the start of `output` and
+    /// returning its length.  `output` is always at least `MAX_MAC_LEN` bytes long.
+    ///
+    /// This allows MACs to be calculated incrementally, without first copying the data into a
+    /// single buffer.  The default implementation concatenates the parts and calls `sign`.
+    fn sign_parts(&self, parts: &[&[u8]], output: &mut [u8]) -> Result<usize, CryptoError> {
+        let mac = self.sign(&parts.concat())?;
+        output
+            .get_mut(..mac.len())
+            .ok_or_else(|| CryptoError::Other(anyhow::Error::msg("MAC is too long")))?
+            .copy_from_slice(&mac);
+        Ok(mac.len())
+    }
+}
+
+/// The length of the longest MAC produced by any supported digest algorithm.
+pub const MAX_MAC_LEN: usize = 64;
+
+/// The future returned by `AsyncHmacKey::sign`.
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, CryptoError>> + Send + 'a>>;
+
+/// An hmac key that signs asynchronously, such as a key held by a local signing agent that
+/// is reached over a socket.
+///
+/// This is used with `Request::make_header_async`.
+pub trait AsyncHmacKey: Send + Sync {
+    fn sign<'a>(&'a self, data: &'a [u8]) -> SignFuture<'a>;
 }
 
 /// Type-erased hash context type.
@@ -78,6 +160,165 @@ pub(crate) fn constant_time_compare(a: &[u8], b: &[u8]) -> bool {
     get_crypographer().constant_time_compare(a, b)
 }
 
+pub(crate) fn hkdf(
+    algorithm: DigestAlgorithm,
+    salt: &[u8],
+    ikm: &[u8],
+    info: &[u8],
+    output: &mut [u8],
+) -> Result<(), CryptoError> {
+    get_crypographer().hkdf(algorithm, salt, ikm, info, output)
+}
+
+pub(crate) fn pbkdf2(
+    algorithm: DigestAlgorithm,
+    password: &[u8],
+    salt: &[u8],
+    iterations: NonZeroU32,
+    output: &mut [u8],
+) -> Result<(), CryptoError> {
+    get_crypographer().pbkdf2(algorithm, password, salt, iterations, output)
+}
+
+pub(crate) fn supports_digest(algorithm: DigestAlgorithm) -> bool {
+    get_crypographer().supports_digest(algorithm)
+}
+
 pub(crate) fn new_hasher(algorithm: DigestAlgorithm) -> Result<Box<dyn Hasher>, CryptoError> {
     get_crypographer().new_hasher(algorithm)
 }
+
+#[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
+mod test {
+    use super::*;
+    use crate::{SHA256, SHA512};
+
+    fn unhex(s: &str) -> Vec<u8> {
+        crate::KeyEncoding::Hex
+            .decode(s)
+            .unwrap()
+            .expose_secret()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_hkdf_rfc5869() {
+        // RFC 5869, test case 1
+        let mut okm = [0u8; 42];
+        hkdf(
+            SHA256,
+            &unhex("000102030405060708090a0b0c"),
+            &[0x0b; 22],
+            &unhex("f0f1f2f3f4f5f6f7f8f9"),
+            &mut okm,
+        )
+        .unwrap();
+        assert_eq!(
+            okm.to_vec(),
+            unhex(
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+                 34007208d5b887185865"
+            )
+        );
+    }
+
+    #[test]
+    fn test_hkdf_rfc5869_no_salt() {
+        // RFC 5869, test case 3
+        let mut okm = [0u8; 42];
+        hkdf(SHA256, &[], &[0x0b; 22], &[], &mut okm).unwrap();
+        assert_eq!(
+            okm.to_vec(),
+            unhex(
+                "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+                 9d201395faa4b61a96c8"
+            )
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2() {
+        let mut output = [0u8; 32];
+        pbkdf2(
+            SHA256,
+            b"password",
+            b"salt",
+            NonZeroU32::new(2).unwrap(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            output.to_vec(),
+            unhex("ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43")
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_sha512() {
+        let mut output = [0u8; 64];
+        pbkdf2(
+            SHA512,
+            b"password",
+            b"salt",
+            NonZeroU32::new(1).unwrap(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            output.to_vec(),
+            unhex(
+                "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252\
+                 c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"
+            )
+        );
+    }
+
+    #[test]
+    fn test_hmac_rfc4231() {
+        // RFC 4231, test cases 2 and 6; the second key is longer than the block size.
+        let cases: [(&[u8], &[u8], &str, &str); 2] = [
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+                 6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+            ),
+        ];
+        for (key, data, sha256, sha512) in cases {
+            for (algorithm, expected) in [(SHA256, unhex(sha256)), (SHA512, unhex(sha512))] {
+                let key = new_key(algorithm, key).unwrap();
+                assert_eq!(key.sign(data).unwrap(), expected);
+
+                // the same MAC, with the data split into several parts
+                let (first, rest) = data.split_at(5);
+                let (second, third) = rest.split_at(rest.len() / 2);
+                let mut output = [0u8; MAX_MAC_LEN];
+                let len = key
+                    .sign_parts(&[first, b"", second, third], &mut output)
+                    .unwrap();
+                assert_eq!(&output[..len], &expected[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sign_parts() {
+        for algorithm in crate::DigestAlgorithm::ALL {
+            let key = new_key(*algorithm, b"secret").unwrap();
+            let mut output = [0u8; MAX_MAC_LEN];
+            let len = key
+                .sign_parts(&[b"hawk", b"", b".1.header\n"], &mut output)
+                .unwrap();
+            assert_eq!(len, algorithm.output_len());
+            assert_eq!(&output[..len], &key.sign(b"hawk.1.header\n").unwrap()[..]);
+        }
+    }
+}
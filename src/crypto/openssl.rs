@@ -1,6 +1,8 @@
 use super::{CryptoError, Cryptographer, Hasher, HmacKey};
 use crate::DigestAlgorithm;
 use std::convert::{TryFrom, TryInto};
+use std::num::NonZeroU32;
+use zeroize::Zeroize;
 
 use openssl::error::ErrorStack;
 use openssl::hash::MessageDigest;
@@ -15,6 +17,7 @@ impl From<ErrorStack> for CryptoError {
 
 pub struct OpensslCryptographer;
 
+// OpenSSL keeps its own copy of the key material, which it cleanses when the `PKey` is freed.
 struct OpensslHmacKey {
     key: PKey<Private>,
     digest: MessageDigest,
@@ -22,12 +25,15 @@ struct OpensslHmacKey {
 
 impl HmacKey for OpensslHmacKey {
     fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        hmac_with_key(self.digest, &self.key, &[data])
+    }
+
+    fn sign_parts(&self, parts: &[&[u8]], output: &mut [u8]) -> Result<usize, CryptoError> {
         let mut hmac_signer = Signer::new(self.digest, &self.key)?;
-        hmac_signer.update(&data)?;
-        let digest = hmac_signer.sign_to_vec()?;
-        let mut mac = vec![0; self.digest.size()];
-        mac.clone_from_slice(digest.as_ref());
-        Ok(mac)
+        for part in parts {
+            hmac_signer.update(part)?;
+        }
+        Ok(hmac_signer.sign(output)?)
     }
 }
 
@@ -83,6 +89,89 @@ impl Cryptographer for OpensslCryptographer {
         let ctx = openssl::hash::Hasher::new(algorithm.try_into()?)?;
         Ok(Box::new(OpensslHasher(Some(ctx))))
     }
+
+    fn hkdf(
+        &self,
+        algorithm: DigestAlgorithm,
+        salt: &[u8],
+        ikm: &[u8],
+        info: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        // HKDF is implemented directly in terms of HMAC, following RFC 5869, as OpenSSL's
+        // HKDF support varies between versions.
+        let digest: MessageDigest = algorithm.try_into()?;
+        let hash_len = digest.size();
+        if output.len() > 255 * hash_len {
+            return Err(CryptoError::Other(anyhow::Error::msg(
+                "HKDF output length too large",
+            )));
+        }
+
+        let salt = if salt.is_empty() {
+            vec![0u8; hash_len]
+        } else {
+            salt.to_vec()
+        };
+        let mut prk = hmac(digest, &salt, &[ikm])?;
+
+        let mut result = Ok(());
+        let mut t: Vec<u8> = vec![];
+        for (i, chunk) in output.chunks_mut(hash_len).enumerate() {
+            let counter = [i as u8 + 1];
+            match hmac(digest, &prk, &[&t, info, &counter]) {
+                Ok(next) => {
+                    t.zeroize();
+                    t = next;
+                    chunk.copy_from_slice(&t[..chunk.len()]);
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        prk.zeroize();
+        t.zeroize();
+        result
+    }
+
+    fn pbkdf2(
+        &self,
+        algorithm: DigestAlgorithm,
+        password: &[u8],
+        salt: &[u8],
+        iterations: NonZeroU32,
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        openssl::pkcs5::pbkdf2_hmac(
+            password,
+            salt,
+            iterations.get() as usize,
+            algorithm.try_into()?,
+            output,
+        )?;
+        Ok(())
+    }
+}
+
+/// Calculate the HMAC of the concatenation of `data` with the given key.
+fn hmac(digest: MessageDigest, key: &[u8], data: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+    hmac_with_key(digest, &PKey::hmac(key)?, data)
+}
+
+/// Calculate the HMAC of the concatenation of `data` with an HMAC `PKey`.
+fn hmac_with_key(
+    digest: MessageDigest,
+    key: &PKey<Private>,
+    data: &[&[u8]],
+) -> Result<Vec<u8>, CryptoError> {
+    let mut signer = Signer::new(digest, key)?;
+    for d in data {
+        signer.update(d)?;
+    }
+    Ok(signer.sign_to_vec()?)
 }
 
 impl TryFrom<DigestAlgorithm> for MessageDigest {
@@ -92,7 +181,6 @@ impl TryFrom<DigestAlgorithm> for MessageDigest {
             DigestAlgorithm::Sha256 => Ok(MessageDigest::sha256()),
             DigestAlgorithm::Sha384 => Ok(MessageDigest::sha384()),
             DigestAlgorithm::Sha512 => Ok(MessageDigest::sha512()),
-            algo => Err(CryptoError::UnsupportedDigest(algo)),
         }
     }
 }
@@ -1,7 +1,8 @@
 use super::{CryptoError, Cryptographer, Hasher, HmacKey};
 use crate::DigestAlgorithm;
-use ring::{digest, hmac};
+use ring::{digest, hkdf, hmac, pbkdf2};
 use std::convert::{TryFrom, TryInto};
+use std::num::NonZeroU32;
 
 impl From<ring::error::Unspecified> for CryptoError {
     // Ring's errors are entirely opaque
@@ -18,6 +19,8 @@ impl From<std::convert::Infallible> for CryptoError {
 
 pub struct RingCryptographer;
 
+// ring does not retain the key material itself, only the HMAC state derived from it.  That
+// state is not zeroized when dropped.
 struct RingHmacKey(hmac::Key);
 
 impl HmacKey for RingHmacKey {
@@ -27,7 +30,19 @@ impl HmacKey for RingHmacKey {
         mac.copy_from_slice(digest.as_ref());
         Ok(mac)
     }
+
+    fn sign_parts(&self, parts: &[&[u8]], output: &mut [u8]) -> Result<usize, CryptoError> {
+        let mut ctx = hmac::Context::with_key(&self.0);
+        for part in parts {
+            ctx.update(part);
+        }
+        let tag = ctx.sign();
+        let mac = tag.as_ref();
+        output[..mac.len()].copy_from_slice(mac);
+        Ok(mac.len())
+    }
 }
+
 // This is always `Some` until `finish` is called.
 struct RingHasher(Option<digest::Context>);
 
@@ -65,6 +80,9 @@ impl Cryptographer for RingCryptographer {
     }
 
     fn constant_time_compare(&self, a: &[u8], b: &[u8]) -> bool {
+        // ring has deprecated this function in favor of external crates, but it remains the
+        // constant-time comparison that ring itself uses.
+        #[allow(deprecated)]
         ring::constant_time::verify_slices_are_equal(a, b).is_ok()
     }
 
@@ -72,6 +90,40 @@ impl Cryptographer for RingCryptographer {
         let ctx = digest::Context::new(algorithm.try_into()?);
         Ok(Box::new(RingHasher(Some(ctx))))
     }
+
+    fn hkdf(
+        &self,
+        algorithm: DigestAlgorithm,
+        salt: &[u8],
+        ikm: &[u8],
+        info: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        let prk = hkdf::Salt::new(algorithm.try_into()?, salt).extract(ikm);
+        prk.expand(&[info], OutputLen(output.len()))?.fill(output)?;
+        Ok(())
+    }
+
+    fn pbkdf2(
+        &self,
+        algorithm: DigestAlgorithm,
+        password: &[u8],
+        salt: &[u8],
+        iterations: NonZeroU32,
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        pbkdf2::derive(algorithm.try_into()?, iterations, salt, password, output);
+        Ok(())
+    }
+}
+
+// ring requires a `KeyType` to describe the length of HKDF output.
+struct OutputLen(usize);
+
+impl hkdf::KeyType for OutputLen {
+    fn len(&self) -> usize {
+        self.0
+    }
 }
 
 impl TryFrom<DigestAlgorithm> for &'static digest::Algorithm {
@@ -95,3 +147,25 @@ impl TryFrom<DigestAlgorithm> for hmac::Algorithm {
         }
     }
 }
+
+impl TryFrom<DigestAlgorithm> for hkdf::Algorithm {
+    type Error = CryptoError;
+    fn try_from(algorithm: DigestAlgorithm) -> Result<Self, CryptoError> {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Ok(hkdf::HKDF_SHA256),
+            DigestAlgorithm::Sha384 => Ok(hkdf::HKDF_SHA384),
+            DigestAlgorithm::Sha512 => Ok(hkdf::HKDF_SHA512),
+        }
+    }
+}
+
+impl TryFrom<DigestAlgorithm> for pbkdf2::Algorithm {
+    type Error = CryptoError;
+    fn try_from(algorithm: DigestAlgorithm) -> Result<Self, CryptoError> {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Ok(pbkdf2::PBKDF2_HMAC_SHA256),
+            DigestAlgorithm::Sha384 => Ok(pbkdf2::PBKDF2_HMAC_SHA384),
+            DigestAlgorithm::Sha512 => Ok(pbkdf2::PBKDF2_HMAC_SHA512),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::crypto::CryptoError;
+use crate::mac::NormalizedString;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -16,6 +17,9 @@ pub enum Error {
     #[error("Missing `nonce` attribute in Hawk header")]
     MissingNonce,
 
+    #[error("{0}")]
+    Parse(#[source] ParseError),
+
     #[error("{0}")]
     InvalidBewit(#[source] InvalidBewit),
 
@@ -27,6 +31,77 @@ pub enum Error {
 
     #[error("Crypto error: {0}")]
     Crypto(#[source] CryptoError),
+
+    #[error("Unknown digest algorithm `{0}`")]
+    UnknownDigestAlgorithm(String),
+
+    #[error("Invalid key encoding: {0}")]
+    InvalidKeyEncoding(String),
+
+    #[error("Invalid ext: {0}")]
+    InvalidExt(String),
+
+    #[error("Invalid payload hash: {0}")]
+    InvalidPayloadHash(String),
+
+    #[error("{0}")]
+    WeakKey(#[source] WeakKey),
+}
+
+/// An error parsing a Hawk header value, giving the byte position in the input at which the
+/// problem was found.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("Unparseable Hawk header: {kind} at position {position}")]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    position: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, position: usize) -> Self {
+        ParseError { kind, position }
+    }
+
+    /// Get the kind of error.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Get the byte position in the input at which the error was found.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// The kinds of `ParseError`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    #[error("expected authentication scheme")]
+    ExpectedScheme,
+    #[error("unexpected authentication scheme `{0}`")]
+    WrongScheme(String),
+    #[error("expected attribute name")]
+    ExpectedName,
+    #[error("expected `=`")]
+    ExpectedEquals,
+    #[error("expected attribute value")]
+    ExpectedValue,
+    #[error("expected `,`")]
+    ExpectedComma,
+    #[error("unterminated quoted string")]
+    UnterminatedQuotedString,
+    #[error("invalid character")]
+    InvalidCharacter,
+    #[error("duplicate attribute `{0}`")]
+    DuplicateAttribute(String),
+    #[error("unknown attribute `{0}`")]
+    UnknownAttribute(String),
+    #[error("invalid value for attribute `{0}`")]
+    InvalidValue(String),
+    #[error("header is longer than {max} bytes")]
+    HeaderTooLong { max: usize },
+    #[error("attribute `{name}` is longer than {max} bytes")]
+    FieldTooLong { name: String, max: usize },
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -45,6 +120,83 @@ pub enum InvalidBewit {
     Ext,
 }
 
+/// The reasons a key can be rejected by a `KeyPolicy`.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum WeakKey {
+    #[error("Key is empty")]
+    Empty,
+    #[error("Key is too short: {len} bytes, but at least {min} are required")]
+    TooShort { len: usize, min: usize },
+    #[error("Key consists of a short repeated pattern")]
+    Repetitive,
+    #[error("Key consists of consecutive byte values")]
+    Sequential,
+    #[error("Key has too little entropy: estimated {estimated_bits} bits, but at least {min_bits} are required")]
+    LowEntropy { estimated_bits: u32, min_bits: u32 },
+}
+
+/// The reasons a request can fail validation against `ValidationOptions` or a `BewitPolicy`.
+#[derive(thiserror::Error, Debug)]
+pub enum ValidationError {
+    #[error("Missing `ts` attribute in Hawk header")]
+    MissingTs,
+    #[error("Missing `nonce` attribute in Hawk header")]
+    MissingNonce,
+    #[error("Missing `mac` attribute in Hawk header")]
+    MissingMac,
+    #[error("Method `{0}` is not allowed")]
+    MethodNotAllowed(String),
+    /// The MAC did not match.  If the `debug_normalized_string` option was set, this includes
+    /// the normalized string that the server signed.
+    #[error("Calculated mac does not match{}", normalized_detail(.normalized))]
+    MacMismatch {
+        normalized: Option<NormalizedString>,
+    },
+    #[error("Missing `hash` attribute in Hawk header")]
+    MissingHash,
+    #[error("No payload hash was given to the request")]
+    HashNotComputed,
+    #[error("Payload hash does not match")]
+    HashMismatch,
+    #[error("Could not hash payload: {0}")]
+    Hashing(#[source] Error),
+    #[error("`app` attribute does not match")]
+    AppMismatch,
+    #[error("`dlg` attribute does not match")]
+    DlgMismatch,
+    #[error("Timestamp is too old: {skew:?} in the past")]
+    TimestampTooOld { skew: std::time::Duration },
+    #[error("Timestamp is too far in the future: {skew:?} ahead")]
+    TimestampInFuture { skew: std::time::Duration },
+    #[error("Nonce has already been used")]
+    NonceReused,
+    #[error("Bewit has expired")]
+    Expired,
+    #[error(
+        "Bewit expires too far in the future: {ttl:?} remaining, but at most {max:?} is allowed"
+    )]
+    TtlTooLong {
+        ttl: std::time::Duration,
+        max: std::time::Duration,
+    },
+    #[error("Missing `ext` in bewit")]
+    MissingExt,
+    #[error("Bewit must not have an `ext`")]
+    UnexpectedExt,
+    #[error("Bewit has already been used")]
+    BewitReused,
+    #[error("Bewit has been revoked")]
+    Revoked,
+}
+
+/// Format the normalized string included in `ValidationError::MacMismatch`, if any.
+fn normalized_detail(normalized: &Option<NormalizedString>) -> String {
+    match normalized {
+        Some(normalized) => format!("; server normalized string: {:?}", normalized.to_string()),
+        None => String::new(),
+    }
+}
+
 impl Error {
     // this cannot be a `From<..>` implementation as that publicly exposes the version of base64
     // used in this crate.
@@ -65,6 +217,18 @@ impl From<CryptoError> for Error {
     }
 }
 
+impl From<WeakKey> for Error {
+    fn from(e: WeakKey) -> Self {
+        Error::WeakKey(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
 impl From<InvalidBewit> for Error {
     fn from(e: InvalidBewit) -> Self {
         Error::InvalidBewit(e)
@@ -0,0 +1,179 @@
+#[cfg(feature = "serde")]
+use crate::b64;
+use crate::error::*;
+use crate::header::Header;
+#[cfg(feature = "serde")]
+use base64::Engine;
+
+/// A codec for structured data carried in the `ext` attribute of headers and bewits.
+///
+/// The `ext` attribute may only contain printable ASCII characters other than `"` and `\`, so
+/// arbitrary data must be encoded before it is sent.  A codec is used with
+/// `RequestBuilder::encode_ext` or `ResponseBuilder::encode_ext` to set the attribute, and with
+/// `Header::decode_ext` or `Bewit::decode_ext` to read it.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{Credentials, Key, RequestBuilder, UrlEncodedExt, SHA256};
+///
+/// let credentials = Credentials {
+///     id: "me".to_string(),
+///     key: Key::new("tok", SHA256).unwrap(),
+/// };
+/// let values = vec![("user".to_string(), "José \"Pepe\"".to_string())];
+/// let header = RequestBuilder::new("GET", "example.com", 443, "/")
+///     .encode_ext(&UrlEncodedExt, &values)
+///     .unwrap()
+///     .request()
+///     .make_header(&credentials)
+///     .unwrap();
+/// assert_eq!(header.decode_ext(&UrlEncodedExt).unwrap(), Some(values));
+/// ```
+pub trait ExtCodec {
+    /// The type of the decoded value.
+    type Value;
+
+    /// Encode a value as an `ext` string.  The result must be a valid `ext` value.
+    fn encode(&self, value: &Self::Value) -> Result<String>;
+
+    /// Decode an `ext` string.
+    fn decode(&self, ext: &str) -> Result<Self::Value>;
+}
+
+/// An `ExtCodec` that passes strings through unchanged, failing to encode strings that are not
+/// valid `ext` values.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RawExt;
+
+impl ExtCodec for RawExt {
+    type Value = String;
+
+    fn encode(&self, value: &String) -> Result<String> {
+        if !Header::is_valid_component(value) {
+            return Err(Error::InvalidExt(
+                "ext may only contain printable ASCII characters other than `\"` and `\\`".into(),
+            ));
+        }
+        Ok(value.clone())
+    }
+
+    fn decode(&self, ext: &str) -> Result<String> {
+        Ok(ext.to_string())
+    }
+}
+
+/// An `ExtCodec` that encodes a sequence of name/value pairs in
+/// `application/x-www-form-urlencoded` format, such as `a=1&b=two+words`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UrlEncodedExt;
+
+impl ExtCodec for UrlEncodedExt {
+    type Value = Vec<(String, String)>;
+
+    fn encode(&self, value: &Self::Value) -> Result<String> {
+        Ok(url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(value)
+            .finish())
+    }
+
+    fn decode(&self, ext: &str) -> Result<Self::Value> {
+        Ok(url::form_urlencoded::parse(ext.as_bytes())
+            .into_owned()
+            .collect())
+    }
+}
+
+/// An `ExtCodec` that encodes a value as JSON, then base64-encodes the result.  This is the
+/// format used by Taskcluster for temporary credentials and authorized scopes.  This is only
+/// available with the `serde` feature.
+#[cfg(feature = "serde")]
+pub struct Base64JsonExt<T>(std::marker::PhantomData<fn() -> T>);
+
+#[cfg(feature = "serde")]
+impl<T> Base64JsonExt<T> {
+    pub fn new() -> Self {
+        Base64JsonExt(std::marker::PhantomData)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Default for Base64JsonExt<T> {
+    fn default() -> Self {
+        Base64JsonExt::new()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Clone for Base64JsonExt<T> {
+    fn clone(&self) -> Self {
+        Base64JsonExt::new()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> std::fmt::Debug for Base64JsonExt<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Base64JsonExt")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> ExtCodec for Base64JsonExt<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    type Value = T;
+
+    fn encode(&self, value: &T) -> Result<String> {
+        let json = serde_json::to_vec(value).map_err(|e| Error::InvalidExt(e.to_string()))?;
+        Ok(b64::STANDARD_ENGINE.encode(json))
+    }
+
+    fn decode(&self, ext: &str) -> Result<T> {
+        let json = b64::STANDARD_ENGINE
+            .decode(ext)
+            .map_err(Error::from_base64_error)?;
+        serde_json::from_slice(&json).map_err(|e| Error::InvalidExt(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(RawExt.encode(&"a b=c".to_string()).unwrap(), "a b=c");
+        assert!(RawExt.encode(&"a\"b".to_string()).is_err());
+        assert!(RawExt.encode(&"caf\u{e9}".to_string()).is_err());
+        assert_eq!(RawExt.decode("a b=c").unwrap(), "a b=c");
+    }
+
+    #[test]
+    fn test_url_encoded() {
+        let value = vec![
+            ("a".to_string(), "1".to_string()),
+            ("quote".to_string(), "\"caf\u{e9}\" \\o/".to_string()),
+        ];
+        let encoded = UrlEncodedExt.encode(&value).unwrap();
+        assert_eq!(encoded, "a=1&quote=%22caf%C3%A9%22+%5Co%2F");
+        assert!(Header::is_valid_component(&encoded));
+        assert_eq!(UrlEncodedExt.decode(&encoded).unwrap(), value);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_base64_json() {
+        let codec = Base64JsonExt::<serde_json::Value>::new();
+        let value = serde_json::json!({"authorizedScopes": ["queue:create-task:*"]});
+        let encoded = codec.encode(&value).unwrap();
+        assert_eq!(
+            encoded,
+            "eyJhdXRob3JpemVkU2NvcGVzIjpbInF1ZXVlOmNyZWF0ZS10YXNrOioiXX0="
+        );
+        assert_eq!(codec.decode(&encoded).unwrap(), value);
+        assert!(codec.decode("!!").is_err());
+        assert!(codec.decode("bm90IGpzb24=").is_err());
+    }
+}
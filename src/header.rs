@@ -1,6 +1,10 @@
+use crate::auth_params::{self, Param, ParseOptions};
 use crate::b64;
+use crate::challenge::ChallengeBuilder;
 use crate::error::*;
+use crate::ext::ExtCodec;
 use crate::mac::Mac;
+use crate::payload::PayloadHash;
 use base64::Engine;
 use std::fmt;
 use std::str::FromStr;
@@ -8,10 +12,13 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Representation of a Hawk `Authorization` header value (the part following "Hawk ").
 ///
-/// Headers can be derived from strings using the `FromStr` trait, and formatted into a
-/// string using the `fmt_header` method.
+/// Headers can be derived from strings using the `FromStr` trait or `parse_authorization`, and
+/// formatted into a string using the `fmt_header` method.
 ///
 /// All fields are optional, although for specific purposes some fields must be present.
+///
+/// With the `serde` feature enabled, headers serialize as a structure of their fields, with `ts`
+/// given in seconds since the epoch and `mac` and `hash` base64-encoded, as in the header itself.
 #[derive(Clone, PartialEq, Debug)]
 pub struct Header {
     pub id: Option<String>,
@@ -19,7 +26,7 @@ pub struct Header {
     pub nonce: Option<String>,
     pub mac: Option<Mac>,
     pub ext: Option<String>,
-    pub hash: Option<Vec<u8>>,
+    pub hash: Option<PayloadHash>,
     pub app: Option<String>,
     pub dlg: Option<String>,
 }
@@ -29,14 +36,16 @@ impl Header {
     ///
     /// This is a low-level function. Headers are more often created from Requests or Responses.
     ///
-    /// Note that none of the string-formatted header components can contain the character `\"`.
+    /// The string-formatted header components (`id`, `nonce`, `ext`, `app` and `dlg`) may only
+    /// contain printable ASCII characters (including space) other than `\"` and `\\`, as
+    /// required by the Hawk specification.
     pub fn new<S>(
         id: Option<S>,
         ts: Option<SystemTime>,
         nonce: Option<S>,
         mac: Option<Mac>,
         ext: Option<S>,
-        hash: Option<Vec<u8>>,
+        hash: Option<PayloadHash>,
         app: Option<S>,
         dlg: Option<S>,
     ) -> Result<Header>
@@ -55,6 +64,89 @@ impl Header {
         })
     }
 
+    /// Parse the attributes of a Hawk header, omitting the `"Hawk "` prefix, with the given
+    /// options.
+    ///
+    /// Attributes are parsed according to RFC 7235: values may be tokens or quoted strings
+    /// containing backslash escapes, and attribute names are case-insensitive.  Duplicate
+    /// attributes are an error.  Errors give the position in `s` at which they occurred.
+    pub fn parse(s: &str, options: &ParseOptions) -> Result<Header> {
+        auth_params::check_header_length(s, options)?;
+        Header::from_params(auth_params::parse_params(s, 0, options)?, options)
+    }
+
+    /// Parse a complete `Authorization` or `Server-Authorization` header value, including the
+    /// `Hawk` scheme.
+    pub fn parse_authorization(s: &str, options: &ParseOptions) -> Result<Header> {
+        auth_params::check_header_length(s, options)?;
+        let start = auth_params::strip_scheme(s)?;
+        Header::from_params(auth_params::parse_params(s, start, options)?, options)
+    }
+
+    fn from_params(params: Vec<Param>, options: &ParseOptions) -> Result<Header> {
+        let mut header = Header {
+            id: None,
+            ts: None,
+            nonce: None,
+            mac: None,
+            ext: None,
+            hash: None,
+            app: None,
+            dlg: None,
+        };
+
+        for param in params {
+            let invalid = || {
+                ParseError::new(
+                    ParseErrorKind::InvalidValue(param.name.to_string()),
+                    param.position,
+                )
+            };
+            let string = || {
+                if Header::is_valid_component(&param.value) {
+                    Ok(Some(param.value.to_string()))
+                } else {
+                    Err(invalid())
+                }
+            };
+            match param.name.to_ascii_lowercase().as_str() {
+                "id" => header.id = string()?,
+                "ts" => {
+                    let epoch = u64::from_str(&param.value).map_err(|_| invalid())?;
+                    let ts = UNIX_EPOCH.checked_add(Duration::from_secs(epoch));
+                    header.ts = Some(ts.ok_or_else(invalid)?);
+                }
+                "nonce" => header.nonce = string()?,
+                "mac" => {
+                    let mac = b64::STANDARD_ENGINE
+                        .decode(param.value.as_bytes())
+                        .map_err(|_| invalid())?;
+                    header.mac = Some(Mac::from(mac));
+                }
+                "ext" => header.ext = string()?,
+                "hash" => {
+                    let hash = b64::STANDARD_ENGINE
+                        .decode(param.value.as_bytes())
+                        .map_err(|_| invalid())?;
+                    header.hash = Some(PayloadHash::from_bytes(hash).map_err(|_| invalid())?);
+                }
+                "app" => header.app = string()?,
+                "dlg" => header.dlg = string()?,
+                _ => {
+                    if !options.allow_unknown_attributes {
+                        return Err(ParseError::new(
+                            ParseErrorKind::UnknownAttribute(param.name.to_string()),
+                            param.position,
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+
+        Ok(header)
+    }
+
     /// Check a header component for validity.
     fn check_component<S>(value: Option<S>) -> Result<Option<String>>
     where
@@ -62,9 +154,11 @@ impl Header {
     {
         if let Some(value) = value {
             let value = value.into();
-            if value.contains('\"') {
+            if !Header::is_valid_component(&value) {
                 return Err(Error::HeaderParseError(
-                    "Hawk headers cannot contain `\\`".into(),
+                    "Hawk header components may only contain printable ASCII characters other \
+                     than `\"` and `\\`"
+                        .into(),
                 ));
             }
             Ok(Some(value))
@@ -73,6 +167,19 @@ impl Header {
         }
     }
 
+    /// Check that a component contains only the characters allowed by the Hawk specification:
+    /// printable ASCII, including space, other than `"` and `\`.
+    pub(crate) fn is_valid_component(value: &str) -> bool {
+        value
+            .bytes()
+            .all(|b| (b' '..=b'~').contains(&b) && b != b'"' && b != b'\\')
+    }
+
+    /// Decode the `ext` attribute with the given codec, returning `None` if it is not present.
+    pub fn decode_ext<C: ExtCodec>(&self, codec: &C) -> Result<Option<C::Value>> {
+        self.ext.as_deref().map(|ext| codec.decode(ext)).transpose()
+    }
+
     /// Format the header for transmission in an Authorization header, omitting the `"Hawk "`
     /// prefix.
     pub fn fmt_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -103,7 +210,12 @@ impl Header {
             sep = ", ";
         }
         if let Some(ref hash) = self.hash {
-            write!(f, "{}hash=\"{}\"", sep, b64::STANDARD_ENGINE.encode(hash))?;
+            write!(
+                f,
+                "{}hash=\"{}\"",
+                sep,
+                b64::STANDARD_ENGINE.encode(hash.as_bytes())
+            )?;
             sep = ", ";
         }
         if let Some(ref app) = self.app {
@@ -117,6 +229,175 @@ impl Header {
     }
 }
 
+/// The kind of header a `HeaderBuilder` is building, which determines the fields it allows.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HeaderShape {
+    Any,
+    ServerAuthorization,
+}
+
+/// A builder for `Header`, with named setters in place of the positional arguments of
+/// `Header::new`.
+///
+/// `HeaderBuilder::request` and `HeaderBuilder::server_authorization` begin with the fields that
+/// an `Authorization` or `Server-Authorization` header requires, and `build` checks that a
+/// `Server-Authorization` header contains no other fields.  `HeaderBuilder::new` places no
+/// requirements on the fields.  `WWW-Authenticate` challenges are represented by `Challenge`,
+/// rather than `Header`, and `HeaderBuilder::challenge` starts building one.
+///
+/// As with `Header::new`, `build` checks that the string-formatted components contain only
+/// characters allowed by the Hawk specification.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::HeaderBuilder;
+/// use hawk::mac::Mac;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mac = Mac::from(vec![7, 22, 226, 240, 84, 78, 49, 75, 115, 144, 70,
+///                          106, 102, 134, 144, 128, 225, 239, 95, 132, 202,
+///                          154, 213, 118, 19, 63, 183, 108, 215, 134, 118, 115]);
+/// let hdr = HeaderBuilder::request("dh37fgj492je",
+///                                  UNIX_EPOCH + Duration::new(1353832234, 0),
+///                                  "j4h3g2",
+///                                  mac)
+///     .ext("my-ext-value")
+///     .build()
+///     .unwrap();
+/// assert_eq!(hdr.id.as_deref(), Some("dh37fgj492je"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HeaderBuilder {
+    id: Option<String>,
+    ts: Option<SystemTime>,
+    nonce: Option<String>,
+    mac: Option<Mac>,
+    ext: Option<String>,
+    hash: Option<PayloadHash>,
+    app: Option<String>,
+    dlg: Option<String>,
+    shape: HeaderShape,
+}
+
+impl HeaderBuilder {
+    /// Create a builder with no fields set, and no requirements on the fields of the result.
+    pub fn new() -> Self {
+        HeaderBuilder {
+            id: None,
+            ts: None,
+            nonce: None,
+            mac: None,
+            ext: None,
+            hash: None,
+            app: None,
+            dlg: None,
+            shape: HeaderShape::Any,
+        }
+    }
+
+    /// Create a builder for a request's `Authorization` header, with the required `id`, `ts`,
+    /// `nonce` and `mac` fields.
+    pub fn request<I, N>(id: I, ts: SystemTime, nonce: N, mac: Mac) -> Self
+    where
+        I: Into<String>,
+        N: Into<String>,
+    {
+        HeaderBuilder::new().id(id).ts(ts).nonce(nonce).mac(mac)
+    }
+
+    /// Create a builder for a `Server-Authorization` header, with the required `mac` field.
+    ///
+    /// Per the JS implementation, this header includes only `mac`, `hash`, and `ext`, so `build`
+    /// fails if any other field is set.
+    pub fn server_authorization(mac: Mac) -> Self {
+        HeaderBuilder {
+            shape: HeaderShape::ServerAuthorization,
+            ..HeaderBuilder::new().mac(mac)
+        }
+    }
+
+    /// Start building a `WWW-Authenticate` challenge, which has no fields until they are set.
+    /// Challenges are represented by `Challenge` rather than `Header`, so `build` on the result
+    /// returns a `Challenge`.
+    pub fn challenge<'a>() -> ChallengeBuilder<'a> {
+        ChallengeBuilder::default()
+    }
+
+    /// Set the `id` field.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the `ts` field.
+    pub fn ts(mut self, ts: SystemTime) -> Self {
+        self.ts = Some(ts);
+        self
+    }
+
+    /// Set the `nonce` field.
+    pub fn nonce<S: Into<String>>(mut self, nonce: S) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Set the `mac` field.
+    pub fn mac(mut self, mac: Mac) -> Self {
+        self.mac = Some(mac);
+        self
+    }
+
+    /// Set the `ext` field.
+    pub fn ext<S: Into<String>>(mut self, ext: S) -> Self {
+        self.ext = Some(ext.into());
+        self
+    }
+
+    /// Set the `hash` field.
+    pub fn hash(mut self, hash: PayloadHash) -> Self {
+        self.hash = Some(hash);
+        self
+    }
+
+    /// Set the `app` field.
+    pub fn app<S: Into<String>>(mut self, app: S) -> Self {
+        self.app = Some(app.into());
+        self
+    }
+
+    /// Set the `dlg` field.
+    pub fn dlg<S: Into<String>>(mut self, dlg: S) -> Self {
+        self.dlg = Some(dlg.into());
+        self
+    }
+
+    /// Build the header, checking its components and, for the `server_authorization` shape,
+    /// that only the allowed fields are present.
+    pub fn build(self) -> Result<Header> {
+        if self.shape == HeaderShape::ServerAuthorization
+            && (self.id.is_some()
+                || self.ts.is_some()
+                || self.nonce.is_some()
+                || self.app.is_some()
+                || self.dlg.is_some())
+        {
+            return Err(Error::HeaderParseError(
+                "Server-Authorization headers may only contain `mac`, `hash`, and `ext`".into(),
+            ));
+        }
+        Header::new(
+            self.id, self.ts, self.nonce, self.mac, self.ext, self.hash, self.app, self.dlg,
+        )
+    }
+}
+
+impl Default for HeaderBuilder {
+    fn default() -> Self {
+        HeaderBuilder::new()
+    }
+}
+
 impl fmt::Display for Header {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.fmt_header(f)
@@ -125,100 +406,121 @@ impl fmt::Display for Header {
 
 impl FromStr for Header {
     type Err = Error;
+
+    /// Parse the attributes of a Hawk header, omitting the `"Hawk "` prefix.  This uses the
+    /// default `ParseOptions`.
     fn from_str(s: &str) -> Result<Header> {
-        let mut p = s;
-
-        // Required attributes
-        let mut id: Option<&str> = None;
-        let mut ts: Option<SystemTime> = None;
-        let mut nonce: Option<&str> = None;
-        let mut mac: Option<Vec<u8>> = None;
-        // Optional attributes
-        let mut hash: Option<Vec<u8>> = None;
-        let mut ext: Option<&str> = None;
-        let mut app: Option<&str> = None;
-        let mut dlg: Option<&str> = None;
-
-        while !p.is_empty() {
-            // Skip whitespace and commas used as separators
-            p = p.trim_start_matches(|c| c == ',' || char::is_whitespace(c));
-            // Find first '=' which delimits attribute name from value
-            let assign_end = p
-                .find('=')
-                .ok_or_else(|| Error::HeaderParseError("Expected '='".into()))?;
-            let attr = &p[..assign_end].trim();
-            if p.len() < assign_end + 1 {
-                return Err(Error::HeaderParseError(
-                    "Missing right hand side of =".into(),
-                ));
-            }
-            p = p[assign_end + 1..].trim_start();
-            if !p.starts_with('\"') {
-                return Err(Error::HeaderParseError("Expected opening quote".into()));
-            }
-            p = &p[1..];
-            // We have poor RFC 7235 compliance here as we ought to support backslash
-            // escaped characters, but hawk doesn't allow this we won't either.  All
-            // strings must be surrounded by ".." and contain no such characters.
-            let end = p.find('\"');
-            let val_end =
-                end.ok_or_else(|| Error::HeaderParseError("Expected closing quote".into()))?;
-            let val = &p[..val_end];
-            match *attr {
-                "id" => id = Some(val),
-                "ts" => {
-                    let epoch = u64::from_str(val)
-                        .map_err(|_| Error::HeaderParseError("Error parsing `ts` field".into()))?;
-                    ts = Some(UNIX_EPOCH + Duration::new(epoch, 0));
-                }
-                "mac" => {
-                    mac = Some(b64::STANDARD_ENGINE.decode(val).map_err(|_| {
-                        Error::HeaderParseError("Error parsing `mac` field".into())
-                    })?);
-                }
-                "nonce" => nonce = Some(val),
-                "ext" => ext = Some(val),
-                "hash" => {
-                    hash = Some(b64::STANDARD_ENGINE.decode(val).map_err(|_| {
-                        Error::HeaderParseError("Error parsing `hash` field".into())
-                    })?);
-                }
-                "app" => app = Some(val),
-                "dlg" => dlg = Some(val),
-                _ => {
-                    return Err(Error::HeaderParseError(format!(
-                        "Invalid Hawk field {}",
-                        *attr
-                    )))
-                }
-            };
-            // Break if we are at end of string, otherwise skip separator
-            if p.len() < val_end + 1 {
-                break;
-            }
-            p = p[val_end + 1..].trim_start();
+        Header::parse(s, &ParseOptions::new())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Header")]
+struct HeaderRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ts: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mac: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ext: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dlg: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Header {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        HeaderRepr {
+            id: self.id.clone(),
+            ts: self
+                .ts
+                .map(|ts| ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()),
+            nonce: self.nonce.clone(),
+            mac: self
+                .mac
+                .as_ref()
+                .map(|mac| b64::STANDARD_ENGINE.encode(mac)),
+            ext: self.ext.clone(),
+            hash: self
+                .hash
+                .as_ref()
+                .map(|hash| b64::STANDARD_ENGINE.encode(hash.as_bytes())),
+            app: self.app.clone(),
+            dlg: self.dlg.clone(),
         }
+        .serialize(serializer)
+    }
+}
 
-        Ok(Header {
-            id: id.map(|id| id.to_string()),
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Header {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error as _;
+        let repr = HeaderRepr::deserialize(deserializer)?;
+        let mac = repr
+            .mac
+            .map(|mac| b64::STANDARD_ENGINE.decode(mac))
+            .transpose()
+            .map_err(|_| D::Error::custom("Error parsing `mac` field"))?;
+        let hash = repr
+            .hash
+            .map(|hash| {
+                b64::STANDARD_ENGINE
+                    .decode(hash)
+                    .ok()
+                    .and_then(|hash| PayloadHash::from_bytes(hash).ok())
+            })
+            .map(|hash| hash.ok_or_else(|| D::Error::custom("Error parsing `hash` field")))
+            .transpose()?;
+        let ts = repr
+            .ts
+            .map(|ts| UNIX_EPOCH.checked_add(Duration::from_secs(ts)))
+            .map(|ts| ts.ok_or_else(|| D::Error::custom("Error parsing `ts` field")))
+            .transpose()?;
+        Header::new(
+            repr.id,
             ts,
-            nonce: nonce.map(|nonce| nonce.to_string()),
-            mac: mac.map(Mac::from),
-            ext: ext.map(|ext| ext.to_string()),
+            repr.nonce,
+            mac.map(Mac::from),
+            repr.ext,
             hash,
-            app: app.map(|app| app.to_string()),
-            dlg: dlg.map(|dlg| dlg.to_string()),
-        })
+            repr.app,
+            repr.dlg,
+        )
+        .map_err(D::Error::custom)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Header;
+    use super::{Header, HeaderBuilder};
+    use crate::challenge::Challenge;
+    use crate::credentials::Key;
+    use crate::error::{Error, ParseErrorKind};
     use crate::mac::Mac;
+    use crate::payload::PayloadHash;
+    use crate::ParseOptions;
     use std::str::FromStr;
     use std::time::{Duration, UNIX_EPOCH};
 
+    fn hash() -> PayloadHash {
+        PayloadHash::new(crate::SHA256, vec![1; 32]).unwrap()
+    }
+
     #[test]
     fn illegal_id() {
         assert!(Header::new(
@@ -294,6 +596,126 @@ mod test {
         .is_err());
     }
 
+    #[test]
+    fn illegal_characters() {
+        for ext in ["caf\u{e9}", "a\nb", "a\tb", "a\\b", "\u{7f}"] {
+            assert!(
+                Header::new(None, None, None, None, Some(ext), None, None, None).is_err(),
+                "{ext:?} should be rejected"
+            );
+        }
+        let ext = "all !#$%&'()*+,-./:;<=>?@[]^_`{|}~ allowed";
+        let s = Header::new(None, None, None, None, Some(ext), None, None, None).unwrap();
+        assert_eq!(Header::from_str(&s.to_string()).unwrap(), s);
+    }
+
+    #[test]
+    fn from_str_illegal_characters() {
+        assert!(Header::from_str("ext=\"caf\u{e9}\"").is_err());
+        assert!(Header::from_str("ext=\"a\tb\"").is_err());
+    }
+
+    #[test]
+    fn parse_lengths() {
+        let long_ext = format!("ext=\"{}\"", "a".repeat(5000));
+        assert!(Header::from_str(&long_ext).is_err());
+        let options = ParseOptions::new().max_header_length(None);
+        assert!(Header::parse(&long_ext, &options).is_ok());
+        let options = options.max_field_length(Some(100));
+        assert!(Header::parse(&long_ext, &options).is_err());
+    }
+
+    #[test]
+    fn builder_request() {
+        let mac = Mac::from(vec![1, 2, 3]);
+        let ts = UNIX_EPOCH + Duration::new(1353832234, 0);
+        let built = HeaderBuilder::request("dh37fgj492je", ts, "j4h3g2", mac.clone())
+            .ext("my-ext-value")
+            .hash(hash())
+            .app("my-app")
+            .dlg("my-dlg")
+            .build()
+            .unwrap();
+        let expected = Header::new(
+            Some("dh37fgj492je"),
+            Some(ts),
+            Some("j4h3g2"),
+            Some(mac),
+            Some("my-ext-value"),
+            Some(hash()),
+            Some("my-app"),
+            Some("my-dlg"),
+        )
+        .unwrap();
+        assert_eq!(built, expected);
+    }
+
+    #[test]
+    fn builder_server_authorization() {
+        let mac = Mac::from(vec![1, 2, 3]);
+        let built = HeaderBuilder::server_authorization(mac.clone())
+            .ext("server-ext")
+            .hash(hash())
+            .build()
+            .unwrap();
+        let expected = Header::new(
+            None,
+            None,
+            None,
+            Some(mac.clone()),
+            Some("server-ext"),
+            Some(hash()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(built, expected);
+
+        // only mac, hash, and ext are allowed
+        assert!(HeaderBuilder::server_authorization(mac.clone())
+            .nonce("j4h3g2")
+            .build()
+            .is_err());
+        assert!(HeaderBuilder::server_authorization(mac)
+            .app("my-app")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn builder_challenge() {
+        let challenge = HeaderBuilder::challenge()
+            .error("Stale timestamp")
+            .build()
+            .unwrap();
+        assert_eq!(challenge, Challenge::new(Some("Stale timestamp")).unwrap());
+        assert!(HeaderBuilder::challenge().error("a\"b").build().is_err());
+
+        let key = Key::new("tok", crate::SHA256).unwrap();
+        let ts = UNIX_EPOCH + Duration::from_secs(1353832234);
+        let challenge = HeaderBuilder::challenge()
+            .timestamp(&key, ts)
+            .build()
+            .unwrap();
+        assert_eq!(
+            challenge,
+            Challenge::new(None::<String>)
+                .unwrap()
+                .with_timestamp(&key, ts)
+                .unwrap()
+        );
+        assert_eq!(challenge.error(), None);
+    }
+
+    #[test]
+    fn builder_validates_components() {
+        assert!(HeaderBuilder::new().ext("a\\b").build().is_err());
+        assert!(HeaderBuilder::new().id("caf\u{e9}").build().is_err());
+        let built = HeaderBuilder::new().ext("ok").build().unwrap();
+        assert_eq!(built.ext.as_deref(), Some("ok"));
+        assert_eq!(built.mac, None);
+    }
+
     #[test]
     fn from_str() {
         let s = Header::from_str(
@@ -388,6 +810,79 @@ mod test {
         assert!(s.dlg.is_none());
     }
 
+    #[test]
+    fn from_str_errors() {
+        let parse_err = |s: &str| match Header::from_str(s) {
+            Err(Error::Parse(e)) => (e.kind().clone(), e.position()),
+            r => panic!("unexpected result {r:?}"),
+        };
+        assert_eq!(
+            parse_err("id=\"a\", id=\"b\""),
+            (ParseErrorKind::DuplicateAttribute("id".to_string()), 8)
+        );
+        assert_eq!(
+            parse_err("id=\"a\", ts=\"abc\""),
+            (ParseErrorKind::InvalidValue("ts".to_string()), 8)
+        );
+        assert_eq!(
+            parse_err("id=\"a\", ts=\"18446744073709551615\""),
+            (ParseErrorKind::InvalidValue("ts".to_string()), 8)
+        );
+        assert_eq!(
+            parse_err("id=\"a\", foo=\"b\""),
+            (ParseErrorKind::UnknownAttribute("foo".to_string()), 8)
+        );
+        assert_eq!(
+            parse_err("ext=\"a\\\"b\""),
+            (ParseErrorKind::InvalidValue("ext".to_string()), 0)
+        );
+        assert_eq!(
+            parse_err("id=\"a\", hash=\"AQIDBA==\""),
+            (ParseErrorKind::InvalidValue("hash".to_string()), 8)
+        );
+        assert_eq!(
+            parse_err("id=\"unterminated"),
+            (ParseErrorKind::UnterminatedQuotedString, 3)
+        );
+    }
+
+    #[test]
+    fn from_str_tokens_and_escapes() {
+        let s = Header::from_str("ID=abc, ts=1353832234, nonce=\"\\j4h3g2\"").unwrap();
+        assert_eq!(s.id, Some("abc".to_string()));
+        assert_eq!(s.ts, Some(UNIX_EPOCH + Duration::new(1353832234, 0)));
+        assert_eq!(s.nonce, Some("j4h3g2".to_string()));
+    }
+
+    #[test]
+    fn parse_unknown_attributes() {
+        let options = ParseOptions::new().allow_unknown_attributes(true);
+        let s = Header::parse("id=\"abc\", future=\"x\"", &options).unwrap();
+        assert_eq!(s.id, Some("abc".to_string()));
+    }
+
+    #[test]
+    fn parse_authorization() {
+        let options = ParseOptions::new();
+        let s =
+            Header::parse_authorization("Hawk id=\"abc\", ts=\"1353832234\"", &options).unwrap();
+        assert_eq!(s.id, Some("abc".to_string()));
+        let s = Header::parse_authorization("hawk  id=\"abc\"", &options).unwrap();
+        assert_eq!(s.id, Some("abc".to_string()));
+
+        match Header::parse_authorization("Basic YWJjOmRlZg==", &options) {
+            Err(Error::Parse(e)) => {
+                assert_eq!(e.kind(), &ParseErrorKind::WrongScheme("Basic".to_string()));
+                assert_eq!(e.position(), 0);
+            }
+            r => panic!("unexpected result {r:?}"),
+        }
+        match Header::parse_authorization("Hawk id=\"abc\", id=\"def\"", &options) {
+            Err(Error::Parse(e)) => assert_eq!(e.position(), 15),
+            r => panic!("unexpected result {r:?}"),
+        }
+    }
+
     #[test]
     fn to_str_no_fields() {
         // must supply a type for S, since it is otherwise unused
@@ -433,7 +928,7 @@ mod test {
                 184, 85, 107, 249, 242, 172, 200, 66, 209, 57, 63, 38, 83,
             ])),
             Some("my-ext-value"),
-            Some(vec![1, 2, 3, 4]),
+            Some(hash()),
             Some("my-app"),
             Some("my-dlg"),
         )
@@ -444,7 +939,8 @@ mod test {
             formatted
                 == "id=\"dh37fgj492je\", ts=\"1353832234\", nonce=\"j4h3g2\", \
                     mac=\"CCO2lSpvIcATFl4rdrBBRVYEnLhVa/nyrMhC0Tk/JlM=\", ext=\"my-ext-value\", \
-                    hash=\"AQIDBA==\", app=\"my-app\", dlg=\"my-dlg\""
+                    hash=\"AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=\", \
+                    app=\"my-app\", dlg=\"my-dlg\""
         )
     }
 
@@ -459,7 +955,7 @@ mod test {
                 184, 85, 107, 249, 242, 172, 200, 66, 209, 57, 63, 38, 83,
             ])),
             Some("my-ext-value"),
-            Some(vec![1, 2, 3, 4]),
+            Some(hash()),
             Some("my-app"),
             Some("my-dlg"),
         )
@@ -469,4 +965,46 @@ mod test {
         let s2 = Header::from_str(&formatted).unwrap();
         assert!(s2 == s);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let s = Header::new(
+            Some("dh37fgj492je"),
+            Some(UNIX_EPOCH + Duration::new(1353832234, 0)),
+            Some("j4h3g2"),
+            Some(Mac::from(vec![
+                8, 35, 182, 149, 42, 111, 33, 192, 19, 22, 94, 43, 118, 176, 65, 69, 86, 4, 156,
+                184, 85, 107, 249, 242, 172, 200, 66, 209, 57, 63, 38, 83,
+            ])),
+            Some("my-ext-value"),
+            Some(hash()),
+            None,
+            None,
+        )
+        .unwrap();
+        let json = serde_json::to_value(&s).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": "dh37fgj492je",
+                "ts": 1353832234,
+                "nonce": "j4h3g2",
+                "mac": "CCO2lSpvIcATFl4rdrBBRVYEnLhVa/nyrMhC0Tk/JlM=",
+                "ext": "my-ext-value",
+                "hash": "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=",
+            })
+        );
+        let s2: Header = serde_json::from_value(json).unwrap();
+        assert_eq!(s2, s);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_invalid() {
+        assert!(serde_json::from_str::<Header>(r#"{"mac": "!!"}"#).is_err());
+        assert!(serde_json::from_str::<Header>(r#"{"id": "a\"b"}"#).is_err());
+        assert!(serde_json::from_str::<Header>(r#"{"hash": "AQIDBA=="}"#).is_err());
+        assert!(serde_json::from_str::<Header>(r#"{"ts": 18446744073709551615}"#).is_err());
+    }
 }
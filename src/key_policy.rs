@@ -0,0 +1,241 @@
+use crate::credentials::DigestAlgorithm;
+use crate::error::*;
+use std::collections::HashMap;
+
+/// A policy for the strength of Hawk keys, used by `Key::new_strict` and
+/// `Key::new_with_policy`.
+///
+/// The default policy requires keys of at least the length recommended for their digest
+/// algorithm (see `DigestAlgorithm::recommended_key_len`) and rejects obviously weak keys: empty
+/// keys, keys consisting of a short repeated pattern (such as a single repeated byte), and keys
+/// consisting of a run of consecutive byte values.  It does not check the estimated entropy of
+/// keys unless `min_entropy_bits` is set.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{Key, KeyPolicy, SHA256};
+///
+/// let policy = KeyPolicy::new().min_length(SHA256, 16).min_entropy_bits(64);
+/// assert!(Key::new_with_policy("tok", SHA256, &policy).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct KeyPolicy {
+    min_lengths: HashMap<DigestAlgorithm, usize>,
+    reject_weak: bool,
+    min_entropy_bits: Option<u32>,
+}
+
+/// Repeated patterns up to this length are considered weak.
+const MAX_WEAK_PATTERN_LEN: usize = 4;
+
+impl KeyPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        KeyPolicy {
+            min_lengths: HashMap::new(),
+            reject_weak: true,
+            min_entropy_bits: None,
+        }
+    }
+
+    /// Set the minimum key length, in bytes, for the given algorithm.
+    pub fn min_length(mut self, algorithm: DigestAlgorithm, len: usize) -> Self {
+        self.min_lengths.insert(algorithm, len);
+        self
+    }
+
+    /// Set whether obviously weak keys are rejected.
+    pub fn reject_weak_keys(mut self, reject: bool) -> Self {
+        self.reject_weak = reject;
+        self
+    }
+
+    /// Require that keys have at least this many bits of estimated entropy.
+    ///
+    /// The estimate is based on the frequency of byte values within the key, and is only a
+    /// heuristic: it cannot detect keys derived from passwords, and a key of `n` bytes is never
+    /// estimated to have more than `n * log2(n)` bits of entropy, so short random keys may be
+    /// rejected by high thresholds.
+    pub fn min_entropy_bits(mut self, bits: u32) -> Self {
+        self.min_entropy_bits = Some(bits);
+        self
+    }
+
+    /// Get the minimum key length, in bytes, for the given algorithm.
+    pub fn get_min_length(&self, algorithm: DigestAlgorithm) -> usize {
+        self.min_lengths
+            .get(&algorithm)
+            .copied()
+            .unwrap_or_else(|| algorithm.recommended_key_len())
+    }
+
+    /// Check the given key material against this policy.
+    pub fn check(&self, key: &[u8], algorithm: DigestAlgorithm) -> Result<()> {
+        if self.reject_weak && key.is_empty() {
+            return Err(WeakKey::Empty.into());
+        }
+
+        let min = self.get_min_length(algorithm);
+        if key.len() < min {
+            return Err(WeakKey::TooShort {
+                len: key.len(),
+                min,
+            }
+            .into());
+        }
+
+        if self.reject_weak && !key.is_empty() {
+            if is_repetitive(key) {
+                return Err(WeakKey::Repetitive.into());
+            }
+            if is_sequential(key) {
+                return Err(WeakKey::Sequential.into());
+            }
+        }
+
+        if let Some(min_bits) = self.min_entropy_bits {
+            let estimated_bits = estimate_entropy_bits(key);
+            if estimated_bits < min_bits {
+                return Err(WeakKey::LowEntropy {
+                    estimated_bits,
+                    min_bits,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        KeyPolicy::new()
+    }
+}
+
+/// Determine whether the key consists of a short pattern, repeated.
+fn is_repetitive(key: &[u8]) -> bool {
+    (1..=MAX_WEAK_PATTERN_LEN)
+        .filter(|period| *period < key.len())
+        .any(|period| key.iter().zip(&key[period..]).all(|(a, b)| a == b))
+}
+
+/// Determine whether the key consists of consecutive byte values, ascending or descending.
+fn is_sequential(key: &[u8]) -> bool {
+    key.len() > 1
+        && (key.windows(2).all(|w| w[1] == w[0].wrapping_add(1))
+            || key.windows(2).all(|w| w[1] == w[0].wrapping_sub(1)))
+}
+
+/// Estimate the entropy of the key, in bits, from the Shannon entropy of its byte values.
+fn estimate_entropy_bits(key: &[u8]) -> u32 {
+    let mut counts = [0usize; 256];
+    for b in key {
+        counts[*b as usize] += 1;
+    }
+    let len = key.len() as f64;
+    let per_byte: f64 = counts
+        .iter()
+        .filter(|c| **c > 0)
+        .map(|c| {
+            let p = *c as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    (per_byte * len).floor() as u32
+}
+
+#[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
+mod test {
+    use super::*;
+    use crate::credentials::Key;
+    use crate::{SHA256, SHA512};
+
+    fn weak_key(result: Result<()>) -> WeakKey {
+        match result {
+            Err(Error::WeakKey(w)) => w,
+            r => panic!("expected a weak key error, got {r:?}"),
+        }
+    }
+
+    #[test]
+    fn test_default_accepts_random() {
+        let (_, material) = Key::generate(SHA512).unwrap();
+        KeyPolicy::new()
+            .check(material.expose_secret(), SHA512)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_too_short() {
+        assert_eq!(
+            weak_key(KeyPolicy::new().check(b"tok", SHA256)),
+            WeakKey::TooShort { len: 3, min: 32 }
+        );
+        KeyPolicy::new()
+            .min_length(SHA256, 3)
+            .check(b"tok", SHA256)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_empty() {
+        let policy = KeyPolicy::new().min_length(SHA256, 0);
+        assert_eq!(weak_key(policy.check(b"", SHA256)), WeakKey::Empty);
+        policy.reject_weak_keys(false).check(b"", SHA256).unwrap();
+    }
+
+    #[test]
+    fn test_repetitive() {
+        let policy = KeyPolicy::new();
+        assert_eq!(
+            weak_key(policy.check(&[99u8; 32], SHA256)),
+            WeakKey::Repetitive
+        );
+        assert_eq!(
+            weak_key(policy.check(b"abcdabcdabcdabcdabcdabcdabcdabcd", SHA256)),
+            WeakKey::Repetitive
+        );
+        policy
+            .reject_weak_keys(false)
+            .check(&[99u8; 32], SHA256)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sequential() {
+        let ascending: Vec<u8> = (0..32).collect();
+        let descending: Vec<u8> = (0..32).rev().collect();
+        let policy = KeyPolicy::new();
+        assert_eq!(
+            weak_key(policy.check(&ascending, SHA256)),
+            WeakKey::Sequential
+        );
+        assert_eq!(
+            weak_key(policy.check(&descending, SHA256)),
+            WeakKey::Sequential
+        );
+    }
+
+    #[test]
+    fn test_low_entropy() {
+        let key = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab";
+        let policy = KeyPolicy::new().min_entropy_bits(64);
+        assert!(matches!(
+            weak_key(policy.check(key, SHA256)),
+            WeakKey::LowEntropy { min_bits: 64, .. }
+        ));
+        let (_, material) = Key::generate(SHA256).unwrap();
+        policy.check(material.expose_secret(), SHA256).unwrap();
+    }
+
+    #[test]
+    fn test_estimate_entropy_bits() {
+        assert_eq!(estimate_entropy_bits(b"aaaa"), 0);
+        assert_eq!(estimate_entropy_bits(b"abcd"), 8);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(estimate_entropy_bits(&all), 2048);
+    }
+}
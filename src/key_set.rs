@@ -0,0 +1,178 @@
+use crate::credentials::Key;
+use std::time::SystemTime;
+
+/// An ordered set of keys for a single Hawk id, used to validate requests while keys are being
+/// rotated.
+///
+/// Each key may have a window, outside of which it is not used for validation.  A typical
+/// rotation adds the new key to the front of the set, and gives the old key a `not_after` time
+/// after which clients are expected to have switched to the new key.
+///
+/// Validation against a key set tries every key that is active at the time of validation,
+/// without stopping at the first match, so the time taken does not reveal which key matched.
+/// The result is a [`KeyMatch`] identifying the key, which can be used to track use of old keys.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{Key, KeySet, RequestBuilder, Credentials, SHA256};
+/// use std::time::{Duration, SystemTime};
+///
+/// let old_key = Key::new("old-token", SHA256).unwrap();
+/// let new_key = Key::new("new-token", SHA256).unwrap();
+/// let keys = KeySet::new()
+///     .with_key(new_key)
+///     .with_key_window(old_key.clone(), None, Some(SystemTime::now() + Duration::from_secs(3600)));
+///
+/// // a client that has not yet switched to the new key
+/// let credentials = Credentials { id: "me".to_string(), key: old_key };
+/// let request = RequestBuilder::new("GET", "example.com", 443, "/").request();
+/// let header = request.make_header(&credentials).unwrap();
+///
+/// let key_match = request
+///     .validate_header_with_keys(&header, &keys, Duration::from_secs(60))
+///     .unwrap();
+/// assert_eq!(key_match.index(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KeySet {
+    entries: Vec<KeySetEntry>,
+}
+
+#[derive(Clone, Debug)]
+struct KeySetEntry {
+    key: Key,
+    not_before: Option<SystemTime>,
+    not_after: Option<SystemTime>,
+}
+
+impl KeySetEntry {
+    fn is_active(&self, now: SystemTime) -> bool {
+        self.not_before.is_none_or(|nb| now >= nb) && self.not_after.is_none_or(|na| now <= na)
+    }
+}
+
+impl KeySet {
+    /// Create a new, empty key set.
+    pub fn new() -> Self {
+        KeySet { entries: vec![] }
+    }
+
+    /// Add a key that is always active.
+    pub fn with_key(self, key: Key) -> Self {
+        self.with_key_window(key, None, None)
+    }
+
+    /// Add a key that is only active from `not_before` until `not_after`, inclusive.
+    pub fn with_key_window(
+        mut self,
+        key: Key,
+        not_before: Option<SystemTime>,
+        not_after: Option<SystemTime>,
+    ) -> Self {
+        self.push(key, not_before, not_after);
+        self
+    }
+
+    /// Add a key that is only active from `not_before` until `not_after`, inclusive.
+    pub fn push(
+        &mut self,
+        key: Key,
+        not_before: Option<SystemTime>,
+        not_after: Option<SystemTime>,
+    ) {
+        self.entries.push(KeySetEntry {
+            key,
+            not_before,
+            not_after,
+        });
+    }
+
+    /// Get the number of keys in the set, whether active or not.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determine whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the key at the given index, as reported by a `KeyMatch`.
+    pub fn get(&self, index: usize) -> Option<&Key> {
+        self.entries.get(index).map(|e| &e.key)
+    }
+
+    /// Iterate over the keys that are active at the given time, with their indexes.
+    pub(crate) fn active_at(&self, now: SystemTime) -> impl Iterator<Item = (usize, &Key)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(move |(_, e)| e.is_active(now))
+            .map(|(i, e)| (i, &e.key))
+    }
+}
+
+impl From<Key> for KeySet {
+    fn from(key: Key) -> Self {
+        KeySet::new().with_key(key)
+    }
+}
+
+/// The result of a successful validation against a [`KeySet`], identifying the key that
+/// matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyMatch {
+    index: usize,
+}
+
+impl KeyMatch {
+    pub(crate) fn new(index: usize) -> Self {
+        KeyMatch { index }
+    }
+
+    /// Get the index of the matching key in the key set.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Determine whether the matching key is the first key in the set, which is usually the
+    /// current key.
+    pub fn is_primary(&self) -> bool {
+        self.index == 0
+    }
+}
+
+#[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn key(k: &str) -> Key {
+        Key::new(k, crate::SHA256).unwrap()
+    }
+
+    #[test]
+    fn test_active_at() {
+        let now = SystemTime::now();
+        let hour = Duration::from_secs(3600);
+        let keys = KeySet::new()
+            .with_key(key("a"))
+            .with_key_window(key("b"), Some(now + hour), None)
+            .with_key_window(key("c"), None, Some(now - hour))
+            .with_key_window(key("d"), Some(now - hour), Some(now + hour));
+        let active: Vec<usize> = keys.active_at(now).map(|(i, _)| i).collect();
+        assert_eq!(active, vec![0, 3]);
+        let active: Vec<usize> = keys.active_at(now + hour * 2).map(|(i, _)| i).collect();
+        assert_eq!(active, vec![0, 1]);
+        assert_eq!(keys.len(), 4);
+        assert!(keys.get(4).is_none());
+    }
+
+    #[test]
+    fn test_from_key() {
+        let keys = KeySet::from(key("a"));
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys.active_at(SystemTime::now()).count(), 1);
+    }
+}
@@ -23,7 +23,7 @@
 //!
 //! // provide the details of the request to be authorized
 //!  let request = RequestBuilder::new("POST", "example.com", 80, "/v1/users")
-//!     .hash(&payload_hash[..])
+//!     .hash(&payload_hash)
 //!     .request();
 //!
 //! // Get the resulting header, including the calculated MAC; this involves a random
@@ -33,7 +33,7 @@
 //! // the header would the be attached to the request
 //! assert_eq!(header.id.unwrap(), "test-client");
 //! assert_eq!(header.mac.unwrap().len(), 32);
-//! assert_eq!(header.hash.unwrap().len(), 32);
+//! assert_eq!(header.hash.unwrap().as_bytes().len(), 32);
 //! ```
 //!
 //! A client that wishes to use a bewit (URL parameter) can do so as follows:
@@ -62,27 +62,30 @@
 //! Request instance, and use the request to validate the header.
 //!
 //! ```
-//! use hawk::{RequestBuilder, Header, Key, SHA256};
+//! use hawk::{RequestBuilder, Header, Key, PayloadHash, PayloadHasher, SHA256};
 //! use hawk::mac::Mac;
 //! use std::time::{Duration, UNIX_EPOCH};
 //!
-//! let mac = Mac::from(vec![7, 22, 226, 240, 84, 78, 49, 75, 115, 144, 70,
-//!                          106, 102, 134, 144, 128, 225, 239, 95, 132, 202,
-//!                          154, 213, 118, 19, 63, 183, 108, 215, 134, 118, 115]);
+//! let mac = Mac::from(vec![146, 92, 28, 177, 128, 33, 109, 215, 211, 179, 130,
+//!                          95, 214, 187, 206, 192, 246, 64, 182, 46, 202, 55,
+//!                          2, 45, 43, 157, 158, 239, 159, 209, 39, 9]);
+//! let client_hash = PayloadHash::new(SHA256, vec![
+//!     47, 26, 175, 135, 244, 71, 206, 38, 183, 190, 225, 194, 14, 206, 148, 216,
+//!     71, 58, 229, 42, 31, 41, 44, 3, 145, 57, 57, 163, 155, 108, 98, 239]).unwrap();
 //! // get the header (usually from the received request; constructed directly here)
 //! let hdr = Header::new(Some("dh37fgj492je"),
 //!                       Some(UNIX_EPOCH + Duration::new(1353832234, 0)),
 //!                       Some("j4h3g2"),
 //!                       Some(mac),
 //!                       Some("my-ext-value"),
-//!                       Some(vec![1, 2, 3, 4]),
+//!                       Some(client_hash),
 //!                       Some("my-app"),
 //!                       Some("my-dlg")).unwrap();
 //!
-//! // build a request object based on what we know
-//! let hash = vec![1, 2, 3, 4];
+//! // build a request object based on what we know, including the hash of the body received
+//! let hash = PayloadHasher::hash("text/plain", SHA256, "request-body").unwrap();
 //! let request = RequestBuilder::new("GET", "localhost", 443, "/resource")
-//!     .hash(&hash[..])
+//!     .hash(&hash)
 //!     .request();
 //!
 //! let key = Key::new(vec![99u8; 32], SHA256).unwrap();
@@ -92,6 +95,24 @@
 //! }
 //! ```
 //!
+//! Headers can also be constructed with `HeaderBuilder`, which names each field:
+//!
+//! ```
+//! use hawk::{HeaderBuilder, Header};
+//! use hawk::mac::Mac;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let ts = UNIX_EPOCH + Duration::new(1353832234, 0);
+//! let mac = Mac::from(vec![1u8; 32]);
+//! let hdr = HeaderBuilder::request("dh37fgj492je", ts, "j4h3g2", mac.clone())
+//!     .ext("my-ext-value")
+//!     .app("my-app")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(hdr, Header::new(Some("dh37fgj492je"), Some(ts), Some("j4h3g2"), Some(mac),
+//!                             Some("my-ext-value"), None, Some("my-app"), None).unwrap());
+//! ```
+//!
 //! A server which validates bewits looks like this:
 //!
 //! ```
@@ -134,16 +155,39 @@
 //!
 //! Attempting to configure both the `use_ring` and `use_openssl` features will
 //! result in a build error.
+//!
+//! The optional `serde` feature adds `Serialize` and `Deserialize` implementations for
+//! [`DigestAlgorithm`], [`Header`], [`Bewit`] and [`CredentialsDescriptor`], and the
+//! `Base64JsonExt` codec for `ext` values.
+//!
+//! The optional `tokio` feature implements `tokio::io::AsyncRead` for [`HashingReader`] and
+//! `tokio::io::AsyncWrite` for [`PayloadHasher`], and the optional `stream` feature adds
+//! `HashingStream` and `PayloadHasher::hash_stream` for hashing a `futures_core::Stream` of
+//! payload chunks.
 
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
 
+mod auth_params;
+pub use crate::auth_params::ParseOptions;
+
 mod header;
-pub use crate::header::Header;
+pub use crate::header::{Header, HeaderBuilder};
+
+mod challenge;
+pub use crate::challenge::{Challenge, ChallengeBuilder};
 
 mod credentials;
-pub use crate::credentials::{Credentials, DigestAlgorithm, Key};
+pub use crate::credentials::{
+    Credentials, CredentialsDescriptor, DigestAlgorithm, Key, KeyEncoding,
+};
+
+mod key_set;
+pub use crate::key_set::{KeyMatch, KeySet};
+
+mod key_policy;
+pub use crate::key_policy::KeyPolicy;
 
 mod request;
 pub use crate::request::{Request, RequestBuilder};
@@ -151,17 +195,37 @@ pub use crate::request::{Request, RequestBuilder};
 mod response;
 pub use crate::response::{Response, ResponseBuilder};
 
+mod ext;
+#[cfg(feature = "serde")]
+pub use crate::ext::Base64JsonExt;
+pub use crate::ext::{ExtCodec, RawExt, UrlEncodedExt};
+
+mod validation;
+pub use crate::validation::{
+    BewitPolicy, BewitStore, Clock, ExtRequirement, MemoryBewitStore, MemoryNonceStore,
+    MemoryRevocationList, NonceStore, PayloadHashRequirement, PendingPayload, RevocationList,
+    SystemClock, ValidationKeys, ValidationOptions,
+};
+
 mod error;
 pub use crate::error::*;
 
+mod secret;
+pub use crate::secret::SecretBytes;
+
 mod payload;
-pub use crate::payload::PayloadHasher;
+#[cfg(feature = "stream")]
+pub use crate::payload::HashingStream;
+pub use crate::payload::{HashingReader, PayloadHash, PayloadHasher};
 
 mod bewit;
 pub use crate::bewit::Bewit;
 
 mod b64;
 
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+
 pub mod mac;
 
 pub mod crypto;
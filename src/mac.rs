@@ -1,8 +1,9 @@
 use crate::b64;
 use crate::credentials::Key;
+use crate::crypto::MAX_MAC_LEN;
 use crate::error::*;
 use base64::Engine;
-use std::io::Write;
+use std::fmt;
 use std::ops::Deref;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -33,42 +34,231 @@ impl Mac {
         hash: Option<&[u8]>,
         ext: Option<&str>,
     ) -> Result<Mac> {
-        // Note: there's a \n after each item.
-        let mut buffer: Vec<u8> = Vec::with_capacity(
-            15 + 1 + // mac_type (worst case since it doesn't really matter)
-            10 + 1 + // ts (in practice this will be 10 bytes)
-            nonce.len() + 1 +
-            host.len() + 1 +
-            6 + 1 + // Longer than 6 bytes of port seems very unlikely
-            path.len() + 1 +
-            hash.map_or(0, |h| h.len() * 4 / 3) + 1 +
-            ext.map_or(0, str::len) + 1,
-        );
+        NormalizedFields::new(mac_type, ts, nonce, method, host, port, path, hash, ext).sign(key)
+    }
+
+    /// Calculate the MAC of a timestamp, as sent in the `tsm` attribute of a `WWW-Authenticate`
+    /// challenge.
+    pub(crate) fn timestamp(key: &Key, ts: SystemTime) -> Result<Mac> {
+        NormalizedString::timestamp(ts).sign(key)
+    }
+}
+
+/// The fields of a normalized string, with numbers and the hash formatted into fixed-size
+/// buffers, so that they can be fed to the HMAC without building the string itself.
+pub(crate) struct NormalizedFields<'a> {
+    mac_type: &'static str,
+    ts: Decimal,
+    nonce: &'a str,
+    method: &'a str,
+    host: &'a str,
+    port: Decimal,
+    path: &'a str,
+    hash: EncodedHash,
+    ext: &'a str,
+}
 
-        writeln!(
-            buffer,
-            "{mac_type}\n{ts}\n{nonce}\n{method}\n{path}\n{host}\n{port}",
-            mac_type = match mac_type {
+impl<'a> NormalizedFields<'a> {
+    pub(crate) fn new(
+        mac_type: MacType,
+        ts: SystemTime,
+        nonce: &'a str,
+        method: &'a str,
+        host: &'a str,
+        port: u16,
+        path: &'a str,
+        hash: Option<&[u8]>,
+        ext: Option<&'a str>,
+    ) -> Self {
+        NormalizedFields {
+            mac_type: match mac_type {
                 MacType::Header => "hawk.1.header",
                 MacType::Response => "hawk.1.response",
                 MacType::Bewit => "hawk.1.bewit",
             },
-            ts = ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
-            nonce = nonce,
-            method = method,
-            path = path,
-            host = host,
-            port = port,
-        )?;
-
-        if let Some(h) = hash {
-            writeln!(buffer, "{}", b64::STANDARD_ENGINE.encode(h),)?;
-        } else {
-            writeln!(buffer)?;
+            ts: Decimal::new(ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()),
+            nonce,
+            method,
+            host,
+            port: Decimal::new(port.into()),
+            path,
+            hash: EncodedHash::new(hash),
+            ext: ext.unwrap_or_default(),
+        }
+    }
+
+    /// Get the parts of the normalized string, in order.  Note that there's a \n after each
+    /// item.
+    fn parts(&self) -> [&[u8]; 18] {
+        [
+            self.mac_type.as_bytes(),
+            b"\n",
+            self.ts.as_bytes(),
+            b"\n",
+            self.nonce.as_bytes(),
+            b"\n",
+            self.method.as_bytes(),
+            b"\n",
+            self.path.as_bytes(),
+            b"\n",
+            self.host.as_bytes(),
+            b"\n",
+            self.port.as_bytes(),
+            b"\n",
+            self.hash.as_bytes(),
+            b"\n",
+            self.ext.as_bytes(),
+            b"\n",
+        ]
+    }
+
+    /// Calculate the MAC of these fields into `output`.
+    fn sign_into<'o>(&self, key: &Key, output: &'o mut [u8; MAX_MAC_LEN]) -> Result<&'o [u8]> {
+        key.sign_parts(&self.parts(), output)
+    }
+
+    /// Calculate the MAC of these fields.
+    pub(crate) fn sign(&self, key: &Key) -> Result<Mac> {
+        let mut output = [0u8; MAX_MAC_LEN];
+        Ok(Mac(self.sign_into(key, &mut output)?.to_vec()))
+    }
+
+    /// Determine whether the MAC of these fields is `expected`, without allocating.
+    pub(crate) fn matches(&self, key: &Key, expected: &Mac) -> Result<bool> {
+        let mut output = [0u8; MAX_MAC_LEN];
+        let calculated = self.sign_into(key, &mut output)?;
+        Ok(crate::crypto::constant_time_compare(calculated, expected))
+    }
+
+    pub(crate) fn to_normalized_string(&self) -> NormalizedString {
+        NormalizedString(self.parts().concat())
+    }
+}
+
+/// A `u64` formatted in decimal, without allocating.
+struct Decimal {
+    buf: [u8; 20],
+    start: usize,
+}
+
+impl Decimal {
+    fn new(mut value: u64) -> Self {
+        let mut buf = [0u8; 20];
+        let mut start = buf.len();
+        loop {
+            start -= 1;
+            buf[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        Decimal { buf, start }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+}
+
+/// A base64-encoded hash, held inline if it is no longer than the largest supported digest.
+enum EncodedHash {
+    Inline { buf: [u8; 88], len: usize },
+    Heap(String),
+}
+
+impl EncodedHash {
+    fn new(hash: Option<&[u8]>) -> Self {
+        let mut buf = [0u8; 88];
+        match hash {
+            None => EncodedHash::Inline { buf, len: 0 },
+            Some(hash) => match b64::STANDARD_ENGINE.encode_slice(hash, &mut buf) {
+                Ok(len) => EncodedHash::Inline { buf, len },
+                Err(_) => EncodedHash::Heap(b64::STANDARD_ENGINE.encode(hash)),
+            },
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            EncodedHash::Inline { buf, len } => &buf[..*len],
+            EncodedHash::Heap(s) => s.as_bytes(),
         }
-        writeln!(buffer, "{}", ext.unwrap_or_default())?;
+    }
+}
+
+/// The normalized string that is signed to produce a MAC: exactly the bytes passed to the
+/// HMAC.
+///
+/// When a MAC fails to validate, comparing the normalized strings calculated by the client and
+/// the server usually shows which component they disagree on.  `Request`, `Response` and the
+/// `debug_normalized_string` validation option provide the normalized string the server
+/// calculated.  It contains only the request components, never the key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct NormalizedString(Vec<u8>);
+
+impl NormalizedString {
+    /// Build the normalized string for a header, response or bewit MAC.
+    pub fn new(
+        mac_type: MacType,
+        ts: SystemTime,
+        nonce: &str,
+        method: &str,
+        host: &str,
+        port: u16,
+        path: &str,
+        hash: Option<&[u8]>,
+        ext: Option<&str>,
+    ) -> Result<NormalizedString> {
+        Ok(
+            NormalizedFields::new(mac_type, ts, nonce, method, host, port, path, hash, ext)
+                .to_normalized_string(),
+        )
+    }
+
+    /// Build the normalized string for the `tsm` attribute of a `WWW-Authenticate` challenge.
+    pub fn timestamp(ts: SystemTime) -> NormalizedString {
+        let normalized = format!(
+            "hawk.1.ts\n{}\n",
+            ts.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+        );
+        NormalizedString(normalized.into_bytes())
+    }
+
+    /// Sign this normalized string with the given key.
+    pub fn sign(&self, key: &Key) -> Result<Mac> {
+        Ok(Mac(key.sign(&self.0)?))
+    }
+
+    /// Get the bytes of the normalized string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Get the bytes of the normalized string, consuming it.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for NormalizedString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for NormalizedString {
+    /// Format the normalized string as text, replacing any invalid UTF-8.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.0))
+    }
+}
 
-        Ok(Mac(key.sign(buffer.as_ref())?))
+impl fmt::Debug for NormalizedString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NormalizedString")
+            .field(&String::from_utf8_lossy(&self.0))
+            .finish()
     }
 }
 
@@ -100,8 +290,9 @@ impl PartialEq for Mac {
 
 #[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
 mod test {
-    use super::{Mac, MacType};
+    use super::{Decimal, Mac, MacType, NormalizedFields, NormalizedString};
     use crate::credentials::Key;
+    use base64::Engine;
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     fn key() -> Key {
@@ -198,4 +389,94 @@ mod test {
                 ]
         );
     }
+
+    #[test]
+    fn test_normalized_string() {
+        let hash = vec![1, 2, 3, 4, 5];
+        let normalized = NormalizedString::new(
+            MacType::Header,
+            sys_time(1000, 100),
+            "nonny",
+            "POST",
+            "mysite.com",
+            443,
+            "/v1/api",
+            Some(&hash),
+            Some("ext"),
+        )
+        .unwrap();
+        assert_eq!(
+            normalized.as_bytes(),
+            b"hawk.1.header\n1000\nnonny\nPOST\n/v1/api\nmysite.com\n443\nAQIDBAU=\next\n"
+        );
+
+        let key = key();
+        let mac = Mac::new(
+            MacType::Header,
+            &key,
+            sys_time(1000, 100),
+            "nonny",
+            "POST",
+            "mysite.com",
+            443,
+            "/v1/api",
+            Some(&hash),
+            Some("ext"),
+        )
+        .unwrap();
+        assert_eq!(normalized.sign(&key).unwrap(), mac);
+    }
+
+    #[test]
+    fn test_normalized_timestamp() {
+        let normalized = NormalizedString::timestamp(sys_time(1353832234, 0));
+        assert_eq!(normalized.to_string(), "hawk.1.ts\n1353832234\n");
+        assert_eq!(
+            format!("{normalized:?}"),
+            "NormalizedString(\"hawk.1.ts\\n1353832234\\n\")"
+        );
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(Decimal::new(0).as_bytes(), b"0");
+        assert_eq!(Decimal::new(443).as_bytes(), b"443");
+        assert_eq!(
+            Decimal::new(u64::MAX).as_bytes(),
+            u64::MAX.to_string().as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_normalized_fields_long() {
+        // hashes longer than any supported digest are still encoded correctly, and long paths
+        // and hashes are signed through `sign_parts` to the same MAC as the complete string
+        let hash = vec![7u8; 100];
+        let path = format!("/{}", "a".repeat(600));
+        let fields = NormalizedFields::new(
+            MacType::Response,
+            sys_time(1000, 0),
+            "nonny",
+            "GET",
+            "mysite.com",
+            80,
+            &path,
+            Some(&hash),
+            None,
+        );
+        let expected = format!(
+            "hawk.1.response\n1000\nnonny\nGET\n{}\nmysite.com\n80\n{}\n\n",
+            path,
+            crate::b64::STANDARD_ENGINE.encode(&hash)
+        );
+        assert_eq!(
+            fields.to_normalized_string().as_bytes(),
+            expected.as_bytes()
+        );
+
+        let key = key();
+        let mac = fields.sign(&key).unwrap();
+        assert!(fields.matches(&key, &mac).unwrap());
+        assert_eq!(mac.as_slice(), &key.sign(expected.as_bytes()).unwrap()[..]);
+    }
 }
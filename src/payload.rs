@@ -1,30 +1,148 @@
 use crate::error::*;
 use crate::{crypto, DigestAlgorithm};
+use std::borrow::Cow;
+use std::io;
+#[cfg(any(feature = "tokio", feature = "stream"))]
+use std::pin::Pin;
+#[cfg(any(feature = "tokio", feature = "stream"))]
+use std::task::{Context, Poll};
+
+/// A payload hash, as calculated by `PayloadHasher` or given in the `hash` attribute of a Hawk
+/// header, along with the digest algorithm that produced it.
+///
+/// Payload hashes are compared in constant time, so that comparing a calculated hash to one
+/// supplied by a client does not reveal how much of the two match.  Hashes produced by
+/// different algorithms are never equal.
+#[derive(Clone, Debug, Eq)]
+pub struct PayloadHash {
+    algorithm: DigestAlgorithm,
+    bytes: Vec<u8>,
+}
+
+impl PayloadHash {
+    /// Create a payload hash from a hash calculated elsewhere with the given algorithm.  This
+    /// fails if the hash is not the length of that algorithm's output.
+    pub fn new<B: Into<Vec<u8>>>(algorithm: DigestAlgorithm, bytes: B) -> Result<Self> {
+        let bytes = bytes.into();
+        if bytes.len() != algorithm.output_len() {
+            return Err(Error::InvalidPayloadHash(format!(
+                "expected {} bytes for {}, got {}",
+                algorithm.output_len(),
+                algorithm,
+                bytes.len()
+            )));
+        }
+        Ok(PayloadHash { algorithm, bytes })
+    }
+
+    /// Create a payload hash from bytes of unknown origin, such as a hash received in a header,
+    /// inferring the algorithm from its length.  This fails if the length is not that of any
+    /// supported algorithm.
+    pub fn from_bytes<B: Into<Vec<u8>>>(bytes: B) -> Result<Self> {
+        let bytes = bytes.into();
+        match DigestAlgorithm::ALL
+            .iter()
+            .find(|algorithm| algorithm.output_len() == bytes.len())
+        {
+            Some(&algorithm) => Ok(PayloadHash { algorithm, bytes }),
+            None => Err(Error::InvalidPayloadHash(format!(
+                "{} bytes is not the length of any supported digest",
+                bytes.len()
+            ))),
+        }
+    }
+
+    /// Get the digest algorithm that produced the hash.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Get the bytes of the hash.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Get the bytes of the hash, consuming it.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl AsRef<[u8]> for PayloadHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<'a> From<&'a PayloadHash> for Cow<'a, PayloadHash> {
+    fn from(hash: &'a PayloadHash) -> Self {
+        Cow::Borrowed(hash)
+    }
+}
+
+impl From<PayloadHash> for Cow<'_, PayloadHash> {
+    fn from(hash: PayloadHash) -> Self {
+        Cow::Owned(hash)
+    }
+}
+
+impl PartialEq for PayloadHash {
+    fn eq(&self, other: &PayloadHash) -> bool {
+        self.algorithm == other.algorithm
+            && crypto::constant_time_compare(&self.bytes, &other.bytes)
+    }
+}
+
 /// A utility for hashing payloads. Feed your entity body to this, then pass the `finish`
 /// result to a request or response.
-pub struct PayloadHasher(Box<dyn crypto::Hasher>);
+///
+/// Large payloads can be hashed as they are transferred: `PayloadHasher` implements
+/// `std::io::Write` (and, with the `tokio` feature, `tokio::io::AsyncWrite`), so it can be the
+/// destination of `std::io::copy`, and [`HashingReader`] hashes data as it is read.  With the
+/// `stream` feature, `PayloadHasher::hash_stream` and `HashingStream` hash a
+/// `futures_core::Stream` of chunks such as `bytes::Bytes`.
+pub struct PayloadHasher {
+    hasher: Box<dyn crypto::Hasher>,
+    algorithm: DigestAlgorithm,
+}
 
 impl PayloadHasher {
-    /// Create a new PayloadHasher. The `content_type` should be lower-case and should
-    /// not include parameters. The digest is assumed to be the same as the digest used
+    /// Create a new PayloadHasher. The digest is assumed to be the same as the digest used
     /// for the credentials in the request.
+    ///
+    /// The `content_type` is normalized as by the JS Hawk implementation: any parameters are
+    /// removed, along with surrounding whitespace, and the result is lower-cased.  So
+    /// `"Application/JSON; charset=utf-8"` is hashed as `"application/json"`.  Use `new_raw` to
+    /// hash the content type exactly as given.
     pub fn new<B>(content_type: B, algorithm: DigestAlgorithm) -> Result<Self>
     where
         B: AsRef<[u8]>,
     {
-        let mut hasher = PayloadHasher(crypto::new_hasher(algorithm)?);
+        PayloadHasher::new_raw(normalize_content_type(content_type.as_ref()), algorithm)
+    }
+
+    /// Create a new PayloadHasher, like `new` but without normalizing the content type.
+    pub fn new_raw<B>(content_type: B, algorithm: DigestAlgorithm) -> Result<Self>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut hasher = PayloadHasher {
+            hasher: crypto::new_hasher(algorithm)?,
+            algorithm,
+        };
         hasher.update(b"hawk.1.payload\n")?;
         hasher.update(content_type.as_ref())?;
         hasher.update(b"\n")?;
         Ok(hasher)
     }
 
-    /// Hash a single value and return it
+    /// Hash a single value and return it.  The content type is normalized as described for
+    /// `new`.
     pub fn hash<B1, B2>(
         content_type: B1,
         algorithm: DigestAlgorithm,
         payload: B2,
-    ) -> Result<Vec<u8>>
+    ) -> Result<PayloadHash>
     where
         B1: AsRef<[u8]>,
         B2: AsRef<[u8]>,
@@ -34,27 +152,251 @@ impl PayloadHasher {
         hasher.finish()
     }
 
+    /// Hash a single value and return it, like `hash` but without normalizing the content type.
+    pub fn hash_raw<B1, B2>(
+        content_type: B1,
+        algorithm: DigestAlgorithm,
+        payload: B2,
+    ) -> Result<PayloadHash>
+    where
+        B1: AsRef<[u8]>,
+        B2: AsRef<[u8]>,
+    {
+        let mut hasher = PayloadHasher::new_raw(content_type, algorithm)?;
+        hasher.update(payload)?;
+        hasher.finish()
+    }
+
     /// Update the hash with new data.
     pub fn update<B>(&mut self, data: B) -> Result<()>
     where
         B: AsRef<[u8]>,
     {
-        self.0.update(data.as_ref())?;
+        self.hasher.update(data.as_ref())?;
         Ok(())
     }
 
     /// Finish hashing and return the result
     ///
     /// Note that this appends a newline to the payload, as does the JS Hawk implementaiton.
-    pub fn finish(mut self) -> Result<Vec<u8>> {
+    pub fn finish(mut self) -> Result<PayloadHash> {
         self.update(b"\n")?;
-        Ok(self.0.finish()?)
+        Ok(PayloadHash {
+            algorithm: self.algorithm,
+            bytes: self.hasher.finish()?,
+        })
+    }
+
+    /// Hash every chunk of the given stream, then finish hashing and return the result.
+    #[cfg(feature = "stream")]
+    pub async fn hash_stream<S, B>(mut self, mut stream: S) -> Result<PayloadHash>
+    where
+        S: futures_core::Stream<Item = B> + Unpin,
+        B: AsRef<[u8]>,
+    {
+        while let Some(chunk) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            self.update(chunk)?;
+        }
+        self.finish()
+    }
+}
+
+/// Normalize a content type as the JS Hawk implementation's `parseContentType` does: take the
+/// portion before any `;`, trim whitespace, and lower-case it.
+fn normalize_content_type(content_type: &[u8]) -> Vec<u8> {
+    let mime = content_type
+        .split(|b| *b == b';')
+        .next()
+        .unwrap_or_default();
+    mime.trim_ascii().to_ascii_lowercase()
+}
+
+impl io::Write for PayloadHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for PayloadHasher {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(io::Write::write(self.get_mut(), buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A reader that hashes the data read through it, passing it on unchanged.
+///
+/// With the `tokio` feature, this also implements `tokio::io::AsyncRead` for readers that do.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{HashingReader, PayloadHasher, SHA256};
+/// use std::io::Read;
+///
+/// let body = &b"a large payload"[..];
+/// let hasher = PayloadHasher::new("text/plain", SHA256).unwrap();
+/// let mut reader = HashingReader::new(body, hasher);
+/// let mut data = vec![];
+/// reader.read_to_end(&mut data).unwrap();
+/// assert_eq!(
+///     reader.finish().unwrap(),
+///     PayloadHasher::hash("text/plain", SHA256, body).unwrap()
+/// );
+/// ```
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: PayloadHasher,
+}
+
+impl<R> HashingReader<R> {
+    /// Create a new reader, reading from `inner` and hashing with `hasher`.
+    pub fn new(inner: R, hasher: PayloadHasher) -> Self {
+        HashingReader { inner, hasher }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying reader.  Data read directly from the underlying
+    /// reader is not hashed.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Split this reader into the underlying reader and the hasher.
+    pub fn into_parts(self) -> (R, PayloadHasher) {
+        (self.inner, self.hasher)
+    }
+
+    /// Finish hashing the data read so far and return the result.  This is typically called once
+    /// the underlying reader is exhausted.
+    pub fn finish(self) -> Result<PayloadHash> {
+        self.hasher.finish()
+    }
+}
+
+impl<R: io::Read> io::Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]).map_err(io::Error::other)?;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for HashingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        std::task::ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.hasher
+            .update(&buf.filled()[before..])
+            .map_err(io::Error::other)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A stream that hashes the chunks passing through it, yielding them unchanged.
+///
+/// Once the stream has ended, call `finish` to get the hash.  The hash is only available for the
+/// complete stream: `finish` fails if the stream has not yet ended, and dropping the stream
+/// discards the hash.  This is available with the `stream` feature.
+#[cfg(feature = "stream")]
+pub struct HashingStream<S> {
+    inner: S,
+    hasher: PayloadHasher,
+    error: Option<Error>,
+    ended: bool,
+}
+
+#[cfg(feature = "stream")]
+impl<S> HashingStream<S> {
+    /// Create a new stream, passing through the chunks of `inner` and hashing with `hasher`.
+    pub fn new(inner: S, hasher: PayloadHasher) -> Self {
+        HashingStream {
+            inner,
+            hasher,
+            error: None,
+            ended: false,
+        }
+    }
+
+    /// Finish hashing and return the result.
+    ///
+    /// This returns an error of kind `std::io::ErrorKind::UnexpectedEof` if the stream has not
+    /// yet ended, as the hash would not cover the whole payload.
+    pub fn finish(self) -> Result<PayloadHash> {
+        if !self.ended {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "HashingStream finished before the stream ended",
+            )));
+        }
+        match self.error {
+            Some(e) => Err(e),
+            None => self.hasher.finish(),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S, B> futures_core::Stream for HashingStream<S>
+where
+    S: futures_core::Stream<Item = B> + Unpin,
+    B: AsRef<[u8]>,
+{
+    type Item = B;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<B>> {
+        let this = self.get_mut();
+        let chunk = std::task::ready!(Pin::new(&mut this.inner).poll_next(cx));
+        match chunk {
+            Some(ref chunk) => {
+                if this.error.is_none() {
+                    if let Err(e) = this.hasher.update(chunk) {
+                        this.error = Some(e);
+                    }
+                }
+            }
+            None => this.ended = true,
+        }
+        Poll::Ready(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
 }
 
 #[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
 mod tests {
-    use super::PayloadHasher;
+    use super::{normalize_content_type, HashingReader, PayloadHash, PayloadHasher};
+    use std::io::{Read, Write};
 
     #[test]
     fn hash_consistency() -> super::Result<()> {
@@ -72,9 +414,10 @@ mod tests {
         let hash4 = // "pàyload" as utf-8 bytes
             PayloadHasher::hash("text/plain", crate::SHA256, vec![112, 195, 160, 121, 108, 111, 97, 100])?;
 
+        assert_eq!(hash1.algorithm(), crate::SHA256);
         assert_eq!(
-            hash1,
-            vec![
+            hash1.as_bytes(),
+            [
                 228, 238, 241, 224, 235, 114, 158, 112, 211, 254, 118, 89, 25, 236, 87, 176, 181,
                 54, 61, 135, 42, 223, 188, 103, 194, 59, 83, 36, 136, 31, 198, 50
             ]
@@ -84,4 +427,160 @@ mod tests {
         assert_eq!(hash4, hash1);
         Ok(())
     }
+
+    #[test]
+    fn payload_hash_eq() {
+        let hash = PayloadHash::new(crate::SHA256, vec![1; 32]).unwrap();
+        assert_eq!(hash, PayloadHash::from_bytes(vec![1; 32]).unwrap());
+        assert_ne!(hash, PayloadHash::new(crate::SHA256, vec![2; 32]).unwrap());
+        assert_eq!(hash.clone().into_bytes(), vec![1; 32]);
+    }
+
+    #[test]
+    fn payload_hash_algorithm() {
+        for algorithm in crate::DigestAlgorithm::ALL {
+            let hash = PayloadHasher::hash("text/plain", *algorithm, "body").unwrap();
+            assert_eq!(hash.algorithm(), *algorithm);
+            assert_eq!(hash.as_bytes().len(), algorithm.output_len());
+            assert_eq!(
+                PayloadHash::from_bytes(hash.as_bytes())
+                    .unwrap()
+                    .algorithm(),
+                *algorithm
+            );
+        }
+
+        assert!(PayloadHash::new(crate::SHA256, vec![1; 48]).is_err());
+        assert!(PayloadHash::from_bytes(vec![1, 2, 3, 4]).is_err());
+        assert!(PayloadHash::from_bytes(vec![]).is_err());
+
+        // hashes with different algorithms are never equal
+        let bytes = vec![1; 48];
+        let sha384 = PayloadHash::new(crate::SHA384, bytes.clone()).unwrap();
+        assert_eq!(sha384, PayloadHash::from_bytes(bytes).unwrap());
+        let sha256 = PayloadHash::new(crate::SHA256, vec![1; 32]).unwrap();
+        assert_ne!(sha384, sha256);
+    }
+
+    #[test]
+    fn content_type_normalization() -> super::Result<()> {
+        assert_eq!(normalize_content_type(b"text/plain"), b"text/plain");
+        assert_eq!(
+            normalize_content_type(b" Application/JSON ; charset=utf-8"),
+            b"application/json"
+        );
+        assert_eq!(normalize_content_type(b"TEXT/HTML;"), b"text/html");
+        assert_eq!(normalize_content_type(b""), b"");
+        assert_eq!(normalize_content_type(b"; charset=utf-8"), b"");
+
+        let expected = PayloadHasher::hash("application/json", crate::SHA256, "{}")?;
+        assert_eq!(
+            PayloadHasher::hash("application/json; charset=utf-8", crate::SHA256, "{}")?,
+            expected
+        );
+        assert_eq!(
+            PayloadHasher::hash_raw("application/json", crate::SHA256, "{}")?,
+            expected
+        );
+        assert_ne!(
+            PayloadHasher::hash_raw("application/json; charset=utf-8", crate::SHA256, "{}")?,
+            expected
+        );
+        Ok(())
+    }
+
+    fn payload() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn hash_write() -> super::Result<()> {
+        let payload = payload();
+        let mut hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        std::io::copy(&mut &payload[..], &mut hasher)?;
+        hasher.flush()?;
+        assert_eq!(
+            hasher.finish()?,
+            PayloadHasher::hash("text/plain", crate::SHA256, &payload)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hash_reader() -> super::Result<()> {
+        let payload = payload();
+        let hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        let mut reader = HashingReader::new(&payload[..], hasher);
+        let mut read = vec![];
+        reader.read_to_end(&mut read)?;
+        assert_eq!(read, payload);
+        assert_eq!(
+            reader.finish()?,
+            PayloadHasher::hash("text/plain", crate::SHA256, &payload)?
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn hash_async_reader_and_writer() -> super::Result<()> {
+        use futures_util::FutureExt;
+        let payload = payload();
+        let expected = PayloadHasher::hash("text/plain", crate::SHA256, &payload)?;
+
+        let hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        let mut reader = HashingReader::new(&payload[..], hasher);
+        let mut read = vec![];
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut read)
+            .now_or_never()
+            .unwrap()?;
+        assert_eq!(read, payload);
+        assert_eq!(reader.finish()?, expected);
+
+        let mut hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        tokio::io::copy(&mut &payload[..], &mut hasher)
+            .now_or_never()
+            .unwrap()?;
+        assert_eq!(hasher.finish()?, expected);
+        Ok(())
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn hash_stream() -> super::Result<()> {
+        use futures_util::{FutureExt, StreamExt};
+        let payload = payload();
+        let expected = PayloadHasher::hash("text/plain", crate::SHA256, &payload)?;
+        let chunks = || {
+            futures_util::stream::iter(
+                payload
+                    .chunks(4096)
+                    .map(bytes::Bytes::copy_from_slice)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        let hash = hasher.hash_stream(chunks()).now_or_never().unwrap()?;
+        assert_eq!(hash, expected);
+
+        let hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        let mut stream = super::HashingStream::new(chunks(), hasher);
+        let mut read = vec![];
+        while let Some(chunk) = stream.next().now_or_never().unwrap() {
+            read.extend_from_slice(&chunk);
+        }
+        assert_eq!(read, payload);
+        assert_eq!(stream.finish()?, expected);
+
+        // finishing before the stream has ended is an error
+        let hasher = PayloadHasher::new("text/plain", crate::SHA256)?;
+        let mut stream = super::HashingStream::new(chunks(), hasher);
+        stream.next().now_or_never().unwrap().unwrap();
+        assert!(matches!(
+            stream.finish(),
+            Err(super::Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+        Ok(())
+    }
 }
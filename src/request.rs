@@ -1,10 +1,18 @@
 use crate::b64;
 use crate::bewit::Bewit;
 use crate::credentials::{Credentials, Key};
+use crate::crypto::AsyncHmacKey;
 use crate::error::*;
-use crate::header::Header;
-use crate::mac::{Mac, MacType};
+use crate::ext::ExtCodec;
+use crate::header::{Header, HeaderBuilder};
+use crate::key_set::{KeyMatch, KeySet};
+use crate::mac::{Mac, MacType, NormalizedFields, NormalizedString};
+use crate::payload::PayloadHash;
 use crate::response::ResponseBuilder;
+use crate::validation::{
+    check_payload_hash, BewitPolicy, CommonOptions, PayloadHashRequirement, PendingPayload,
+    ValidationKeys, ValidationOptions,
+};
 use base64::Engine;
 use log::debug;
 use std::borrow::Cow;
@@ -17,8 +25,10 @@ use url::{Position, Url};
 ///
 /// The structure is created using (RequestBuilder)[struct.RequestBuilder.html]. Most uses of this
 /// library will hold several of the fields in this structure fixed. Cloning the structure with
-/// these fields applied is a convenient way to avoid repeating those fields. Most fields are
-/// references, since in common use the values already exist and will outlive the request.
+/// these fields applied is a convenient way to avoid repeating those fields. Fields are usually
+/// borrowed, since in common use the values already exist and will outlive the request, but
+/// `into_owned` converts a request into a `Request<'static>` that can be stored or sent between
+/// tasks.
 ///
 /// A request can be used on the client, to generate a header or a bewit, or on the server, to
 /// validate the same.
@@ -35,14 +45,14 @@ use url::{Position, Url};
 /// See the documentation in the crate root for examples of creating and validating headers.
 #[derive(Debug, Clone)]
 pub struct Request<'a> {
-    method: &'a str,
-    host: &'a str,
+    method: Cow<'a, str>,
+    host: Cow<'a, str>,
     port: u16,
     path: Cow<'a, str>,
-    hash: Option<&'a [u8]>,
-    ext: Option<&'a str>,
-    app: Option<&'a str>,
-    dlg: Option<&'a str>,
+    hash: Option<Cow<'a, PayloadHash>>,
+    ext: Option<Cow<'a, str>>,
+    app: Option<Cow<'a, str>>,
+    dlg: Option<Cow<'a, str>>,
 }
 
 impl<'a> Request<'a> {
@@ -70,23 +80,79 @@ impl<'a> Request<'a> {
             &credentials.key,
             ts,
             &nonce,
-            self.method,
-            self.host,
+            &self.method,
+            &self.host,
             self.port,
-            self.path.as_ref(),
-            self.hash,
-            self.ext,
+            &self.path,
+            self.hash.as_deref().map(PayloadHash::as_bytes),
+            self.ext.as_deref(),
         )?;
-        Header::new(
-            Some(credentials.id.clone()),
-            Some(ts),
-            Some(nonce),
-            Some(mac),
-            self.ext.map(|v| v.to_string()),
-            self.hash.map(|v| v.to_vec()),
-            self.app.map(|v| v.to_string()),
-            self.dlg.map(|v| v.to_string()),
-        )
+        self.header_builder(credentials.id.clone(), ts, nonce, mac)
+            .build()
+    }
+
+    /// Create a new Header for this request, like `make_header`, but signing with an
+    /// asynchronous key such as a remote signing agent.
+    pub async fn make_header_async<K>(&self, id: &str, key: &K) -> Result<Header>
+    where
+        K: AsyncHmacKey + ?Sized,
+    {
+        let nonce = random_string(10)?;
+        self.make_header_full_async(id, key, SystemTime::now(), nonce)
+            .await
+    }
+
+    /// Similar to `make_header_async`, but allowing specification of the timestamp and nonce.
+    pub async fn make_header_full_async<K, S>(
+        &self,
+        id: &str,
+        key: &K,
+        ts: SystemTime,
+        nonce: S,
+    ) -> Result<Header>
+    where
+        K: AsyncHmacKey + ?Sized,
+        S: Into<String>,
+    {
+        let nonce = nonce.into();
+        let normalized = NormalizedString::new(
+            MacType::Header,
+            ts,
+            &nonce,
+            &self.method,
+            &self.host,
+            self.port,
+            &self.path,
+            self.hash.as_deref().map(PayloadHash::as_bytes),
+            self.ext.as_deref(),
+        )?;
+        let mac = Mac::from(key.sign(normalized.as_bytes()).await?);
+        self.header_builder(id, ts, nonce, mac).build()
+    }
+
+    /// Start building a request header with the given fields, along with the `ext`, `hash`,
+    /// `app` and `dlg` from this request.
+    fn header_builder<I: Into<String>>(
+        &self,
+        id: I,
+        ts: SystemTime,
+        nonce: String,
+        mac: Mac,
+    ) -> HeaderBuilder {
+        let mut builder = HeaderBuilder::request(id, ts, nonce, mac);
+        if let Some(ref ext) = self.ext {
+            builder = builder.ext(ext.as_ref());
+        }
+        if let Some(ref hash) = self.hash {
+            builder = builder.hash(hash.as_ref().clone());
+        }
+        if let Some(ref app) = self.app {
+            builder = builder.app(app.as_ref());
+        }
+        if let Some(ref dlg) = self.dlg {
+            builder = builder.dlg(dlg.as_ref());
+        }
+        builder
     }
 
     /// Make a "bewit" that can be attached to a URL to authenticate GET access.
@@ -101,14 +167,20 @@ impl<'a> Request<'a> {
             &credentials.key,
             exp,
             "",
-            self.method,
-            self.host,
+            &self.method,
+            &self.host,
             self.port,
-            self.path.as_ref(),
-            self.hash,
-            self.ext,
+            &self.path,
+            self.hash.as_deref().map(PayloadHash::as_bytes),
+            self.ext.as_deref(),
         )?;
-        let bewit = Bewit::new(&credentials.id, exp, mac, self.ext);
+        // borrow `ext` for the lifetime of the bewit if possible
+        let ext = match self.ext {
+            Some(Cow::Borrowed(ext)) => Some(Cow::Borrowed(ext)),
+            Some(Cow::Owned(ref ext)) => Some(Cow::Owned(ext.clone())),
+            None => None,
+        };
+        let bewit = Bewit::from_parts(Cow::Borrowed(&credentials.id), exp, mac, ext);
         Ok(bewit)
     }
 
@@ -135,86 +207,176 @@ impl<'a> Request<'a> {
     ///
     /// If a hash has been supplied, then the header must contain a matching hash. Note that this
     /// hash must be calculated based on the request body, not copied from the request header!
+    ///
+    /// For more control over validation, and for the reason validation failed, use
+    /// `validate_header_with_options`.
     pub fn validate_header(&self, header: &Header, key: &Key, ts_skew: Duration) -> bool {
-        // extract required fields, returning early if they are not present
-        let ts = match header.ts {
-            Some(ts) => ts,
-            None => {
-                debug!("missing timestamp from header");
-                return false;
-            }
-        };
-        let nonce = match header.nonce {
-            Some(ref nonce) => nonce,
-            None => {
-                debug!("missing nonce from header");
-                return false;
-            }
-        };
-        let header_mac = match header.mac {
-            Some(ref mac) => mac,
-            None => {
-                debug!("missing mac from header");
-                return false;
-            }
-        };
-        let header_hash = header.hash.as_ref().map(|hash| &hash[..]);
-        let header_ext = header.ext.as_ref().map(|ext| &ext[..]);
+        let options = ValidationOptions::new().ts_skew(ts_skew);
+        self.validate_header_with_options(header, key, &options)
+            .is_ok()
+    }
 
-        // first verify the MAC
-        match Mac::new(
-            MacType::Header,
-            key,
-            ts,
-            nonce,
-            self.method,
-            self.host,
-            self.port,
-            self.path.as_ref(),
-            header_hash,
-            header_ext,
-        ) {
-            Ok(calculated_mac) => {
-                if &calculated_mac != header_mac {
-                    debug!("calculated mac doesn't match header");
-                    return false;
-                }
-            }
+    /// Validate the given header against a set of keys, as during key rotation.
+    ///
+    /// This behaves like `validate_header`, but tries each key in the set that is currently
+    /// active, returning a `KeyMatch` identifying the key that matched, or `None` if validation
+    /// failed.
+    pub fn validate_header_with_keys(
+        &self,
+        header: &Header,
+        keys: &KeySet,
+        ts_skew: Duration,
+    ) -> Option<KeyMatch> {
+        let options = ValidationOptions::new().ts_skew(ts_skew);
+        self.validate_header_with_options(header, keys, &options)
+            .ok()
+    }
+
+    /// Validate the given header according to the given `ValidationOptions`, returning the
+    /// reason for any failure.
+    ///
+    /// The header is validated against either a single `Key` or a `KeySet`; in the latter case,
+    /// only keys that are active according to the options' clock are tried.  The returned
+    /// `KeyMatch` identifies the key that matched, and is always the primary key when a single
+    /// `Key` is given.
+    ///
+    /// If the options include a nonce store, the nonce is recorded only once all other checks
+    /// have succeeded.
+    pub fn validate_header_with_options<K>(
+        &self,
+        header: &Header,
+        keys: &K,
+        options: &ValidationOptions,
+    ) -> std::result::Result<KeyMatch, ValidationError>
+    where
+        K: ValidationKeys + ?Sized,
+    {
+        let result = self.validate_header_inner(header, keys, options, options.payload_hash);
+        if let Err(ref e) = result {
+            debug!("header validation failed: {}", e);
+        }
+        result
+    }
+
+    /// Authenticate the given header without verifying the payload hash, so that a request with
+    /// an invalid MAC can be rejected before its body is read.
+    ///
+    /// This performs all of the checks of `validate_header_with_options` except those related to
+    /// the payload, including recording the nonce.  Any hash given to this `Request` is ignored.
+    /// The returned `PendingPayload` holds the hash claimed by the header, and must be completed
+    /// once the body has been hashed, using `PendingPayload::verify_hasher` or
+    /// `PendingPayload::verify_hash`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hawk::{Credentials, Key, PayloadHasher, RequestBuilder, ValidationOptions, SHA256};
+    ///
+    /// let credentials = Credentials {
+    ///     id: "me".to_string(),
+    ///     key: Key::new("tok", SHA256).unwrap(),
+    /// };
+    /// let hash = PayloadHasher::hash("text/plain", SHA256, "body").unwrap();
+    /// let header = RequestBuilder::new("POST", "example.com", 443, "/")
+    ///     .hash(&hash)
+    ///     .request()
+    ///     .make_header(&credentials)
+    ///     .unwrap();
+    ///
+    /// // on the server, authenticate the header before reading the body..
+    /// let req = RequestBuilder::new("POST", "example.com", 443, "/").request();
+    /// let pending = req
+    ///     .authenticate_header(&header, &credentials.key, &ValidationOptions::new())
+    ///     .unwrap();
+    ///
+    /// // ..then hash the body as it is read
+    /// let mut hasher = PayloadHasher::new("text/plain", SHA256).unwrap();
+    /// hasher.update("body").unwrap();
+    /// assert!(pending.verify_hasher(hasher).is_ok());
+    /// ```
+    pub fn authenticate_header<K>(
+        &self,
+        header: &Header,
+        keys: &K,
+        options: &ValidationOptions,
+    ) -> std::result::Result<PendingPayload, ValidationError>
+    where
+        K: ValidationKeys + ?Sized,
+    {
+        let result =
+            self.validate_header_inner(header, keys, options, PayloadHashRequirement::Ignore);
+        match result {
+            Ok(key_match) => Ok(PendingPayload::new(
+                key_match,
+                header.hash.clone(),
+                options.payload_hash,
+            )),
             Err(e) => {
-                debug!("unexpected mac error: {:?}", e);
-                return false;
+                debug!("header authentication failed: {}", e);
+                Err(e)
             }
-        };
+        }
+    }
+
+    fn validate_header_inner<K>(
+        &self,
+        header: &Header,
+        keys: &K,
+        options: &ValidationOptions,
+        payload_hash: PayloadHashRequirement,
+    ) -> std::result::Result<KeyMatch, ValidationError>
+    where
+        K: ValidationKeys + ?Sized,
+    {
+        // extract required fields, returning early if they are not present
+        let ts = header.ts.ok_or(ValidationError::MissingTs)?;
+        let nonce = header.nonce.as_ref().ok_or(ValidationError::MissingNonce)?;
+        let header_mac = header.mac.as_ref().ok_or(ValidationError::MissingMac)?;
+        let header_hash = header.hash.as_ref();
+
+        if !options.common.method_allowed(&self.method) {
+            return Err(ValidationError::MethodNotAllowed(self.method.to_string()));
+        }
+
+        // first verify the MAC
+        let now = options.common.now();
+        let fields = self.header_fields(ts, nonce, header);
+        let matched = find_matching_key(keys.active_keys(now), header_mac, &fields)
+            .ok_or_else(|| mac_mismatch(&fields, options.common.debug_normalized_string))?;
 
         // ..then the hashes
-        if let Some(local_hash) = self.hash {
-            if let Some(server_hash) = header_hash {
-                if local_hash != server_hash {
-                    debug!("server hash doesn't match header");
-                    return false;
-                }
-            } else {
-                debug!("missing hash from header");
-                return false;
-            }
+        check_payload_hash(payload_hash, self.hash.as_deref(), header_hash)?;
+
+        // ..then app and dlg
+        if options.require_app_match && header.app.as_deref() != self.app.as_deref() {
+            return Err(ValidationError::AppMismatch);
+        }
+        if options.require_dlg_match && header.dlg.as_deref() != self.dlg.as_deref() {
+            return Err(ValidationError::DlgMismatch);
         }
 
         // ..then the timestamp
-        let now = SystemTime::now();
-        let skew = if now > ts {
-            now.duration_since(ts).unwrap()
+        if now > ts {
+            let skew = now.duration_since(ts).unwrap();
+            if skew > options.max_past_skew {
+                return Err(ValidationError::TimestampTooOld { skew });
+            }
         } else {
-            ts.duration_since(now).unwrap()
-        };
-        if skew > ts_skew {
-            debug!(
-                "bad timestamp skew, timestamp too old? detected skew: {:?}, ts_skew: {:?}",
-                &skew, &ts_skew
-            );
-            return false;
+            let skew = ts.duration_since(now).unwrap();
+            if skew > options.max_future_skew {
+                return Err(ValidationError::TimestampInFuture { skew });
+            }
+        }
+
+        // ..and finally the nonce
+        if let Some(ref store) = options.nonce_store {
+            let id = header.id.as_deref().unwrap_or_default();
+            if !store.check_and_insert(id, nonce, ts, now) {
+                return Err(ValidationError::NonceReused);
+            }
         }
 
-        true
+        Ok(KeyMatch::new(matched))
     }
 
     /// Validate the given bewit matches this request.
@@ -224,38 +386,174 @@ impl<'a> Request<'a> {
     ///
     /// Nonces and hashes do not apply when using bewits.
     pub fn validate_bewit(&self, bewit: &Bewit, key: &Key) -> bool {
-        let calculated_mac = Mac::new(
-            MacType::Bewit,
-            key,
-            bewit.exp(),
-            "",
-            self.method,
-            self.host,
-            self.port,
-            self.path.as_ref(),
-            self.hash,
-            match bewit.ext() {
-                Some(e) => Some(e),
-                None => None,
-            },
-        );
-        let calculated_mac = match calculated_mac {
-            Ok(m) => m,
-            Err(_) => {
-                return false;
+        self.validate_bewit_with_options(bewit, key, &ValidationOptions::new())
+            .is_ok()
+    }
+
+    /// Validate the given bewit against a set of keys, as during key rotation.
+    ///
+    /// This behaves like `validate_bewit`, but tries each key in the set that is currently
+    /// active, returning a `KeyMatch` identifying the key that matched, or `None` if validation
+    /// failed.
+    pub fn validate_bewit_with_keys(&self, bewit: &Bewit, keys: &KeySet) -> Option<KeyMatch> {
+        self.validate_bewit_with_options(bewit, keys, &ValidationOptions::new())
+            .ok()
+    }
+
+    /// Validate the given bewit according to the given `ValidationOptions`, returning the reason
+    /// for any failure.
+    ///
+    /// Of the options, only the allowed methods and the clock apply to bewits.  Servers accepting
+    /// bewits should generally prefer `validate_bewit_with_policy`, which can also limit their
+    /// lifetime.
+    pub fn validate_bewit_with_options<K>(
+        &self,
+        bewit: &Bewit,
+        keys: &K,
+        options: &ValidationOptions,
+    ) -> std::result::Result<KeyMatch, ValidationError>
+    where
+        K: ValidationKeys + ?Sized,
+    {
+        self.validate_bewit_inner(bewit, keys, &options.common, options.common.now())
+    }
+
+    /// Validate the given bewit according to the given `BewitPolicy`, returning the reason for
+    /// any failure.
+    ///
+    /// In addition to the MAC and expiration time, this checks the request method, the
+    /// remaining lifetime of the bewit, the presence of `ext`, revocations, and whether the
+    /// bewit has already been used, as configured by the policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hawk::{BewitPolicy, Credentials, Key, RequestBuilder, ValidationError, SHA256};
+    /// use std::time::Duration;
+    ///
+    /// let credentials = Credentials {
+    ///     id: "me".to_string(),
+    ///     key: Key::new("tok", SHA256).unwrap(),
+    /// };
+    /// let req = RequestBuilder::new("GET", "mysite.com", 443, "/resource").request();
+    /// let policy = BewitPolicy::new().max_ttl(Duration::from_secs(3600));
+    ///
+    /// let bewit = req.make_bewit_with_ttl(&credentials, Duration::from_secs(60)).unwrap();
+    /// assert!(req.validate_bewit_with_policy(&bewit, &credentials.key, &policy).is_ok());
+    ///
+    /// // a bewit valid for a year is rejected
+    /// let bewit = req
+    ///     .make_bewit_with_ttl(&credentials, Duration::from_secs(365 * 24 * 3600))
+    ///     .unwrap();
+    /// assert!(matches!(
+    ///     req.validate_bewit_with_policy(&bewit, &credentials.key, &policy),
+    ///     Err(ValidationError::TtlTooLong { .. })
+    /// ));
+    /// ```
+    pub fn validate_bewit_with_policy<K>(
+        &self,
+        bewit: &Bewit,
+        keys: &K,
+        policy: &BewitPolicy,
+    ) -> std::result::Result<KeyMatch, ValidationError>
+    where
+        K: ValidationKeys + ?Sized,
+    {
+        let now = policy.common.now();
+        let key_match = self.validate_bewit_inner(bewit, keys, &policy.common, now)?;
+        policy.check(bewit.exp(), bewit.ext(), now)?;
+
+        // ..then revocations
+        if let Some(ref revocations) = policy.revocations {
+            if revocations.is_revoked(bewit.id(), bewit.exp(), now) {
+                return Err(ValidationError::Revoked);
             }
-        };
+        }
 
-        if bewit.mac() != &calculated_mac {
-            return false;
+        // ..and finally single use, so that only bewits which are otherwise valid are recorded
+        if let Some(ref store) = policy.bewit_store {
+            if !store.check_and_insert(bewit.mac(), bewit.exp(), now) {
+                return Err(ValidationError::BewitReused);
+            }
+        }
+
+        Ok(key_match)
+    }
+
+    fn validate_bewit_inner<K>(
+        &self,
+        bewit: &Bewit,
+        keys: &K,
+        common: &CommonOptions,
+        now: SystemTime,
+    ) -> std::result::Result<KeyMatch, ValidationError>
+    where
+        K: ValidationKeys + ?Sized,
+    {
+        if !common.method_allowed(&self.method) {
+            return Err(ValidationError::MethodNotAllowed(self.method.to_string()));
         }
 
-        let now = SystemTime::now();
+        let fields = self.bewit_fields(bewit);
+        let matched = find_matching_key(keys.active_keys(now), bewit.mac(), &fields)
+            .ok_or_else(|| mac_mismatch(&fields, common.debug_normalized_string))?;
+
         if bewit.exp() < now {
-            return false;
+            return Err(ValidationError::Expired);
         }
 
-        true
+        Ok(KeyMatch::new(matched))
+    }
+
+    /// Get the normalized string that the MAC in the given header signs, if the header was made
+    /// for this request.
+    ///
+    /// This is useful for debugging MAC mismatches, by comparing it to the normalized string
+    /// calculated by the client.  The header must have `ts` and `nonce` attributes.
+    pub fn normalized_header_string(&self, header: &Header) -> Result<NormalizedString> {
+        let ts = header.ts.ok_or(Error::MissingTs)?;
+        let nonce = header.nonce.as_ref().ok_or(Error::MissingNonce)?;
+        Ok(self.header_fields(ts, nonce, header).to_normalized_string())
+    }
+
+    /// Get the normalized string that the MAC in the given bewit signs, if the bewit was made
+    /// for this request.
+    pub fn normalized_bewit_string(&self, bewit: &Bewit) -> Result<NormalizedString> {
+        Ok(self.bewit_fields(bewit).to_normalized_string())
+    }
+
+    fn header_fields<'h>(
+        &'h self,
+        ts: SystemTime,
+        nonce: &'h str,
+        header: &'h Header,
+    ) -> NormalizedFields<'h> {
+        NormalizedFields::new(
+            MacType::Header,
+            ts,
+            nonce,
+            &self.method,
+            &self.host,
+            self.port,
+            &self.path,
+            header.hash.as_ref().map(PayloadHash::as_bytes),
+            header.ext.as_deref(),
+        )
+    }
+
+    fn bewit_fields<'h>(&'h self, bewit: &'h Bewit) -> NormalizedFields<'h> {
+        // note that this includes `hash` even though it must always be None for bewits
+        NormalizedFields::new(
+            MacType::Bewit,
+            bewit.exp(),
+            "",
+            &self.method,
+            &self.host,
+            self.port,
+            &self.path,
+            self.hash.as_deref().map(PayloadHash::as_bytes),
+            bewit.ext(),
+        )
     }
 
     /// Get a Response instance for a response to this request.  This is a convenience
@@ -263,12 +561,27 @@ impl<'a> Request<'a> {
     pub fn make_response_builder(&'a self, req_header: &'a Header) -> ResponseBuilder<'a> {
         ResponseBuilder::from_request_header(
             req_header,
-            self.method,
-            self.host,
+            self.method.as_ref(),
+            self.host.as_ref(),
             self.port,
             self.path.as_ref(),
         )
     }
+
+    /// Convert this request into one that owns all of its fields, and thus can be stored or
+    /// sent between tasks without regard to the lifetime of the values it was built from.
+    pub fn into_owned(self) -> Request<'static> {
+        Request {
+            method: Cow::Owned(self.method.into_owned()),
+            host: Cow::Owned(self.host.into_owned()),
+            port: self.port,
+            path: Cow::Owned(self.path.into_owned()),
+            hash: self.hash.map(|v| Cow::Owned(v.into_owned())),
+            ext: self.ext.map(|v| Cow::Owned(v.into_owned())),
+            app: self.app.map(|v| Cow::Owned(v.into_owned())),
+            dlg: self.dlg.map(|v| Cow::Owned(v.into_owned())),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -276,12 +589,19 @@ pub struct RequestBuilder<'a>(Request<'a>);
 
 impl<'a> RequestBuilder<'a> {
     /// Create a new request with the given method, host, port, and path.
-    pub fn new(method: &'a str, host: &'a str, port: u16, path: &'a str) -> Self {
+    ///
+    /// The method, host, and path may be borrowed (`&str`) or owned (`String`).
+    pub fn new<M, H, P>(method: M, host: H, port: u16, path: P) -> Self
+    where
+        M: Into<Cow<'a, str>>,
+        H: Into<Cow<'a, str>>,
+        P: Into<Cow<'a, str>>,
+    {
         RequestBuilder(Request {
-            method,
-            host,
+            method: method.into(),
+            host: host.into(),
             port,
-            path: Cow::Borrowed(path),
+            path: path.into(),
             hash: None,
             ext: None,
             app: None,
@@ -290,11 +610,16 @@ impl<'a> RequestBuilder<'a> {
     }
 
     /// Create a new request with the host, port, and path determined from the URL.
-    pub fn from_url(method: &'a str, url: &'a Url) -> Result<Self> {
+    ///
+    /// The resulting builder borrows from the URL; use `into_owned` if it must outlive the URL.
+    pub fn from_url<M>(method: M, url: &'a Url) -> Result<Self>
+    where
+        M: Into<Cow<'a, str>>,
+    {
         let (host, port, path) = RequestBuilder::parse_url(url)?;
         Ok(RequestBuilder(Request {
-            method,
-            host,
+            method: method.into(),
+            host: Cow::Borrowed(host),
             port,
             path: Cow::Borrowed(path),
             hash: None,
@@ -305,20 +630,20 @@ impl<'a> RequestBuilder<'a> {
     }
 
     /// Set the request method. This should be a capitalized string.
-    pub fn method(mut self, method: &'a str) -> Self {
-        self.0.method = method;
+    pub fn method<M: Into<Cow<'a, str>>>(mut self, method: M) -> Self {
+        self.0.method = method.into();
         self
     }
 
     /// Set the URL path for the request.
-    pub fn path(mut self, path: &'a str) -> Self {
-        self.0.path = Cow::Borrowed(path);
+    pub fn path<P: Into<Cow<'a, str>>>(mut self, path: P) -> Self {
+        self.0.path = path.into();
         self
     }
 
     /// Set the URL hostname for the request
-    pub fn host(mut self, host: &'a str) -> Self {
-        self.0.host = host;
+    pub fn host<H: Into<Cow<'a, str>>>(mut self, host: H) -> Self {
+        self.0.host = host.into();
         self
     }
 
@@ -335,26 +660,33 @@ impl<'a> RequestBuilder<'a> {
     }
 
     /// Set the content hash for the request
-    pub fn hash<H: Into<Option<&'a [u8]>>>(mut self, hash: H) -> Self {
-        self.0.hash = hash.into();
+    pub fn hash<H: Into<Cow<'a, PayloadHash>>>(mut self, hash: H) -> Self {
+        self.0.hash = Some(hash.into());
         self
     }
 
     /// Set the `ext` Hawk property for the request
-    pub fn ext<S: Into<Option<&'a str>>>(mut self, ext: S) -> Self {
-        self.0.ext = ext.into();
+    pub fn ext<S: Into<Cow<'a, str>>>(mut self, ext: S) -> Self {
+        self.0.ext = Some(ext.into());
         self
     }
 
+    /// Set the `ext` Hawk property for the request to the given value, encoded with the given
+    /// codec.
+    pub fn encode_ext<C: ExtCodec>(mut self, codec: &C, value: &C::Value) -> Result<Self> {
+        self.0.ext = Some(Cow::Owned(codec.encode(value)?));
+        Ok(self)
+    }
+
     /// Set the `app` Hawk property for the request
-    pub fn app<S: Into<Option<&'a str>>>(mut self, app: S) -> Self {
-        self.0.app = app.into();
+    pub fn app<S: Into<Cow<'a, str>>>(mut self, app: S) -> Self {
+        self.0.app = Some(app.into());
         self
     }
 
     /// Set the `dlg` Hawk property for the request
-    pub fn dlg<S: Into<Option<&'a str>>>(mut self, dlg: S) -> Self {
-        self.0.dlg = dlg.into();
+    pub fn dlg<S: Into<Cow<'a, str>>>(mut self, dlg: S) -> Self {
+        self.0.dlg = Some(dlg.into());
         self
     }
 
@@ -363,6 +695,11 @@ impl<'a> RequestBuilder<'a> {
         self.0
     }
 
+    /// Convert this builder into one that owns all of its fields.  See `Request::into_owned`.
+    pub fn into_owned(self) -> RequestBuilder<'static> {
+        RequestBuilder(self.0.into_owned())
+    }
+
     /// Extract the `bewit` query parameter, if any, from the path, and return it in the output
     /// parameter, returning a modified RequestBuilder omitting the `bewit=..` query parameter.  If
     /// no bewit is present, or if an error is returned, the output parameter is reset to None.
@@ -414,6 +751,37 @@ impl<'a> RequestBuilder<'a> {
     }
 }
 
+/// Find the first of the given keys with which the MAC of `fields` is the expected MAC.
+///
+/// Every key is tried, even after a match is found, so that the time taken does not depend on
+/// which key matched.
+fn find_matching_key<'k, I>(keys: I, expected: &Mac, fields: &NormalizedFields) -> Option<usize>
+where
+    I: IntoIterator<Item = (usize, &'k Key)>,
+{
+    let mut matched = None;
+    for (i, key) in keys {
+        match fields.matches(key, expected) {
+            Ok(is_match) => {
+                if is_match && matched.is_none() {
+                    matched = Some(i);
+                }
+            }
+            Err(e) => {
+                debug!("unexpected mac error: {:?}", e);
+            }
+        }
+    }
+    matched
+}
+
+/// Build the error for a MAC that did not match, including the normalized string if asked to.
+fn mac_mismatch(fields: &NormalizedFields, debug_normalized_string: bool) -> ValidationError {
+    ValidationError::MacMismatch {
+        normalized: debug_normalized_string.then(|| fields.to_normalized_string()),
+    }
+}
+
 /// Create a random string with `bytes` bytes of entropy.  The string
 /// is base64-encoded. so it will be longer than bytes characters.
 fn random_string(bytes: usize) -> Result<String> {
@@ -427,10 +795,16 @@ mod test {
     use super::*;
     use crate::credentials::{Credentials, Key};
     use crate::header::Header;
+    use crate::validation::ExtRequirement;
+    use futures_util::FutureExt;
     use std::str::FromStr;
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
     use url::Url;
 
+    fn test_hash(byte: u8) -> PayloadHash {
+        PayloadHash::new(crate::SHA256, vec![byte; 32]).unwrap()
+    }
+
     // this is a header from a real request using the JS Hawk library, to
     // https://pulse.taskcluster.net:443/v1/namespaces with credentials "me" / "tok"
     const REAL_HEADER: &str = "id=\"me\", ts=\"1491183061\", nonce=\"RVnYzW\", \
@@ -458,9 +832,9 @@ mod test {
 
     #[test]
     fn test_builder() {
-        let hash = vec![0u8];
+        let hash = test_hash(0);
         let req = RequestBuilder::new("GET", "example.com", 443, "/foo")
-            .hash(Some(&hash[..]))
+            .hash(&hash)
             .ext("ext")
             .app("app")
             .dlg("dlg")
@@ -470,10 +844,10 @@ mod test {
         assert_eq!(req.path, "/foo");
         assert_eq!(req.host, "example.com");
         assert_eq!(req.port, 443);
-        assert_eq!(req.hash, Some(&hash[..]));
-        assert_eq!(req.ext, Some("ext"));
-        assert_eq!(req.app, Some("app"));
-        assert_eq!(req.dlg, Some("dlg"));
+        assert_eq!(req.hash.as_deref(), Some(&hash));
+        assert_eq!(req.ext.as_deref(), Some("ext"));
+        assert_eq!(req.app.as_deref(), Some("app"));
+        assert_eq!(req.dlg.as_deref(), Some("dlg"));
     }
 
     #[test]
@@ -488,6 +862,62 @@ mod test {
         assert_eq!(req2.path, "/bar");
     }
 
+    fn owned_request() -> RequestBuilder<'static> {
+        let url = Url::parse("https://example.com/foo?a=1").unwrap();
+        let ext = String::from("ext");
+        RequestBuilder::from_url(String::from("POST"), &url)
+            .unwrap()
+            .ext(ext.as_str())
+            .into_owned()
+    }
+
+    #[test]
+    fn test_into_owned() {
+        fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+        let bldr = owned_request();
+        assert_send_static(&bldr);
+        let req = bldr.request();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.host, "example.com");
+        assert_eq!(req.path, "/foo?a=1");
+        assert_eq!(req.ext.as_deref(), Some("ext"));
+
+        let req = RequestBuilder::new(String::from("GET"), "example.com", 443, "/")
+            .host(String::from("other.com"))
+            .path(String::from("/bar"))
+            .request()
+            .into_owned();
+        assert_send_static(&req);
+        assert_eq!(req.host, "other.com");
+        assert_eq!(req.path, "/bar");
+
+        // the optional fields also accept owned values
+        let hash = PayloadHash::new(crate::SHA256, vec![1; 32]).unwrap();
+        let bldr: RequestBuilder<'static> = RequestBuilder::new("GET", "example.com", 443, "/")
+            .hash(hash.clone())
+            .ext(String::from("ext"))
+            .app(String::from("app"))
+            .dlg(String::from("dlg"));
+        let owned = bldr.request();
+        assert_eq!(owned.hash.as_deref(), Some(&hash));
+        assert_eq!(owned.ext.as_deref(), Some("ext"));
+        assert_eq!(owned.app.as_deref(), Some("app"));
+        assert_eq!(owned.dlg.as_deref(), Some("dlg"));
+
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let bewit = req
+            .make_bewit_with_ttl(&credentials, Duration::from_secs(60))
+            .unwrap()
+            .into_owned();
+        drop(credentials);
+        assert_send_static(&bewit);
+        assert_eq!(bewit.id(), "me");
+    }
+
     #[test]
     fn test_url_builder() {
         let url = Url::parse("https://example.com/foo").unwrap();
@@ -724,9 +1154,9 @@ mod test {
 
     #[test]
     fn test_make_header_full_with_optional_fields() {
-        let hash = vec![0u8];
+        let hash = test_hash(0);
         let req = RequestBuilder::new("GET", "example.com", 443, "/foo")
-            .hash(Some(&hash[..]))
+            .hash(&hash)
             .ext("ext")
             .app("app")
             .dlg("dlg")
@@ -745,8 +1175,8 @@ mod test {
                 ts: Some(UNIX_EPOCH + Duration::new(1000, 100)),
                 nonce: Some("nonny".to_string()),
                 mac: Some(Mac::from(vec![
-                    72, 123, 243, 214, 145, 81, 129, 54, 183, 90, 22, 136, 192, 146, 208, 53, 216,
-                    138, 145, 94, 175, 204, 217, 8, 77, 16, 202, 50, 10, 144, 133, 162
+                    160, 192, 126, 103, 129, 159, 204, 237, 50, 194, 255, 63, 103, 54, 169, 135,
+                    197, 112, 69, 118, 77, 103, 142, 181, 218, 187, 161, 251, 9, 8, 125, 49
                 ])),
                 ext: Some("ext".to_string()),
                 hash: Some(hash.clone()),
@@ -756,6 +1186,59 @@ mod test {
         );
     }
 
+    // A signer standing in for a remote agent, which signs with a local key.
+    struct AgentSigner(Key);
+
+    impl AsyncHmacKey for AgentSigner {
+        fn sign<'a>(&'a self, data: &'a [u8]) -> crate::crypto::SignFuture<'a> {
+            Box::pin(async move {
+                self.0.sign(data).map_err(|_| {
+                    crate::crypto::CryptoError::Other(anyhow::Error::msg("sign failed"))
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_make_header_full_async() {
+        let hash = test_hash(0);
+        let req = RequestBuilder::new("GET", "example.com", 443, "/foo")
+            .hash(&hash)
+            .ext("ext")
+            .app("app")
+            .dlg("dlg")
+            .request();
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new(vec![99u8; 32], crate::SHA256).unwrap(),
+        };
+        let signer = AgentSigner(credentials.key.clone());
+        let ts = UNIX_EPOCH + Duration::new(1000, 100);
+        let header = req
+            .make_header_full_async("me", &signer, ts, "nonny")
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            header,
+            req.make_header_full(&credentials, ts, "nonny").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_make_header_async_validates() {
+        let req = RequestBuilder::new("POST", "example.com", 443, "/foo").request();
+        let key = Key::new("tok", crate::SHA256).unwrap();
+        let signer: Box<dyn AsyncHmacKey> = Box::new(AgentSigner(key.clone()));
+        let header = req
+            .make_header_async("me", &*signer)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.id.as_deref(), Some("me"));
+        assert!(req.validate_header(&header, &key, Duration::from_secs(60)));
+    }
+
     #[test]
     fn test_validate_matches_generated() {
         let req = RequestBuilder::new("GET", "example.com", 443, "/foo").request();
@@ -844,11 +1327,11 @@ mod test {
             Some(UNIX_EPOCH + Duration::new(1353832234, 0)),
             Some("j4h3g2"),
             Some(Mac::from(vec![
-                189, 53, 155, 244, 203, 150, 255, 238, 135, 144, 186, 93, 6, 189, 184, 21, 150,
-                210, 226, 61, 93, 154, 17, 218, 142, 250, 254, 193, 123, 132, 131, 195,
+                101, 165, 200, 36, 75, 168, 243, 90, 177, 193, 13, 54, 136, 77, 253, 172, 30, 161,
+                111, 247, 240, 230, 194, 70, 42, 228, 237, 136, 179, 222, 139, 21,
             ])),
             None,
-            Some(vec![1, 2, 3, 4]),
+            Some(test_hash(1)),
             None,
             None,
         )
@@ -880,10 +1363,8 @@ mod test {
     #[test]
     fn test_validate_hash_required_but_not_given() {
         let header = make_header_without_hash();
-        let hash = vec![1, 2, 3, 4];
-        let req = RequestBuilder::new("", "", 0, "")
-            .hash(Some(&hash[..]))
-            .request();
+        let hash = test_hash(1);
+        let req = RequestBuilder::new("", "", 0, "").hash(&hash).request();
         assert!(!req.validate_header(
             &header,
             &Key::new("tok", crate::SHA256).unwrap(),
@@ -894,10 +1375,8 @@ mod test {
     #[test]
     fn test_validate_hash_validated() {
         let header = make_header_with_hash();
-        let hash = vec![1, 2, 3, 4];
-        let req = RequestBuilder::new("", "", 0, "")
-            .hash(Some(&hash[..]))
-            .request();
+        let hash = test_hash(1);
+        let req = RequestBuilder::new("", "", 0, "").hash(&hash).request();
         assert!(req.validate_header(
             &header,
             &Key::new("tok", crate::SHA256).unwrap(),
@@ -905,10 +1384,8 @@ mod test {
         ));
 
         // ..but supplying the wrong hash will cause validation to fail
-        let hash = vec![99, 99, 99, 99];
-        let req = RequestBuilder::new("", "", 0, "")
-            .hash(Some(&hash[..]))
-            .request();
+        let hash = test_hash(99);
+        let req = RequestBuilder::new("", "", 0, "").hash(&hash).request();
         assert!(!req.validate_header(
             &header,
             &Key::new("tok", crate::SHA256).unwrap(),
@@ -916,6 +1393,408 @@ mod test {
         ));
     }
 
+    struct FixedClock(SystemTime);
+
+    impl crate::Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+    }
+
+    fn options_at(secs: u64) -> ValidationOptions {
+        ValidationOptions::new().clock(std::sync::Arc::new(FixedClock(
+            UNIX_EPOCH + Duration::from_secs(secs),
+        )))
+    }
+
+    #[test]
+    fn test_validate_with_options_payload_hash() {
+        let key = Key::new("tok", crate::SHA256).unwrap();
+        let header = make_header_with_hash();
+        let options = options_at(1353832234);
+        let req = RequestBuilder::new("", "", 0, "").request();
+        assert!(req
+            .validate_header_with_options(&header, &key, &options)
+            .is_ok());
+
+        let required = options
+            .clone()
+            .payload_hash(PayloadHashRequirement::Required);
+        assert!(matches!(
+            req.validate_header_with_options(&header, &key, &required),
+            Err(ValidationError::HashNotComputed)
+        ));
+        let hash = test_hash(1);
+        let req = RequestBuilder::new("", "", 0, "").hash(&hash).request();
+        assert!(req
+            .validate_header_with_options(&header, &key, &required)
+            .is_ok());
+        assert!(matches!(
+            req.validate_header_with_options(&make_header_without_hash(), &key, &required),
+            Err(ValidationError::MissingHash)
+        ));
+
+        let hash = test_hash(99);
+        let req = RequestBuilder::new("", "", 0, "").hash(&hash).request();
+        assert!(matches!(
+            req.validate_header_with_options(&header, &key, &options),
+            Err(ValidationError::HashMismatch)
+        ));
+        let ignore = options.payload_hash(PayloadHashRequirement::Ignore);
+        assert!(req
+            .validate_header_with_options(&header, &key, &ignore)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_with_options_skew() {
+        let key = Key::new("tok", crate::SHA256).unwrap();
+        let header = make_header_without_hash();
+        let req = RequestBuilder::new("", "", 0, "").request();
+
+        let options = options_at(1353832234 + 30)
+            .max_past_skew(Duration::from_secs(10))
+            .max_future_skew(Duration::from_secs(60));
+        assert!(matches!(
+            req.validate_header_with_options(&header, &key, &options),
+            Err(ValidationError::TimestampTooOld { skew }) if skew == Duration::from_secs(30)
+        ));
+
+        let options = options_at(1353832234 - 30)
+            .max_past_skew(Duration::from_secs(60))
+            .max_future_skew(Duration::from_secs(10));
+        assert!(matches!(
+            req.validate_header_with_options(&header, &key, &options),
+            Err(ValidationError::TimestampInFuture { skew }) if skew == Duration::from_secs(30)
+        ));
+        let options = options.max_future_skew(Duration::from_secs(30));
+        assert!(req
+            .validate_header_with_options(&header, &key, &options)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_with_options_nonce() {
+        let key = Key::new("tok", crate::SHA256).unwrap();
+        let header = make_header_without_hash();
+        let req = RequestBuilder::new("", "", 0, "").request();
+        let options = options_at(1353832234).nonce_store(std::sync::Arc::new(
+            crate::MemoryNonceStore::new(Duration::from_secs(120)),
+        ));
+
+        // a failed validation does not consume the nonce
+        let wrong_key = Key::new("wrong", crate::SHA256).unwrap();
+        assert!(matches!(
+            req.validate_header_with_options(&header, &wrong_key, &options),
+            Err(ValidationError::MacMismatch { normalized: None })
+        ));
+        assert!(req
+            .validate_header_with_options(&header, &key, &options)
+            .is_ok());
+        assert!(matches!(
+            req.validate_header_with_options(&header, &key, &options),
+            Err(ValidationError::NonceReused)
+        ));
+    }
+
+    #[test]
+    fn test_validate_with_options_method_and_app() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let req = RequestBuilder::new("DELETE", "example.com", 443, "/foo")
+            .app("my-app")
+            .request();
+        let header = req.make_header(&credentials).unwrap();
+
+        let options = ValidationOptions::new().allowed_methods(["GET", "POST"]);
+        assert!(matches!(
+            req.validate_header_with_options(&header, &credentials.key, &options),
+            Err(ValidationError::MethodNotAllowed(method)) if method == "DELETE"
+        ));
+
+        let options = ValidationOptions::new().require_app_match(true);
+        assert!(req
+            .validate_header_with_options(&header, &credentials.key, &options)
+            .is_ok());
+        let other = RequestBuilder::new("DELETE", "example.com", 443, "/foo")
+            .app("other-app")
+            .request();
+        assert!(matches!(
+            other.validate_header_with_options(&header, &credentials.key, &options),
+            Err(ValidationError::AppMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_authenticate_header_then_payload() {
+        let key = Key::new("tok", crate::SHA256).unwrap();
+        let header = make_header_with_hash();
+        let req = RequestBuilder::new("", "", 0, "").request();
+        let options = options_at(1353832234);
+
+        let authenticate = || req.authenticate_header(&header, &key, &options).unwrap();
+        let pending = authenticate();
+        assert_eq!(pending.claimed_hash(), Some(&test_hash(1)));
+        assert!(pending.key_match().is_primary());
+        assert!(pending.verify_hash(&test_hash(1)).is_ok());
+        assert!(matches!(
+            authenticate().verify_hash(&test_hash(2)),
+            Err(ValidationError::HashMismatch)
+        ));
+        let pending = authenticate();
+        let hasher = crate::PayloadHasher::new("text/plain", crate::SHA256).unwrap();
+        assert!(matches!(
+            pending.verify_hasher(hasher),
+            Err(ValidationError::HashMismatch)
+        ));
+
+        // a header without a hash cannot match a payload
+        let header = make_header_without_hash();
+        let authenticate = || req.authenticate_header(&header, &key, &options).unwrap();
+        let pending = authenticate();
+        assert_eq!(pending.claimed_hash(), None);
+        assert!(matches!(
+            pending.verify_hash(&test_hash(1)),
+            Err(ValidationError::MissingHash)
+        ));
+        assert!(authenticate().verify_empty().is_ok());
+        let header = make_header_with_hash();
+
+        let required = options.payload_hash(PayloadHashRequirement::Required);
+        let pending = req.authenticate_header(&header, &key, &required).unwrap();
+        assert!(matches!(
+            pending.verify_empty(),
+            Err(ValidationError::HashNotComputed)
+        ));
+    }
+
+    #[test]
+    fn test_authenticate_header_bad_mac() {
+        let key = Key::new("wrong", crate::SHA256).unwrap();
+        let req = RequestBuilder::new("", "", 0, "").request();
+        assert!(matches!(
+            req.authenticate_header(&make_header_with_hash(), &key, &options_at(1353832234)),
+            Err(ValidationError::MacMismatch { normalized: None })
+        ));
+    }
+
+    #[test]
+    fn test_validate_debug_normalized_string() {
+        let key = Key::new("wrong", crate::SHA256).unwrap();
+        let header = make_header_without_hash();
+        let req = RequestBuilder::new("", "", 0, "").request();
+        let normalized = req.normalized_header_string(&header).unwrap();
+        assert_eq!(
+            normalized.to_string(),
+            "hawk.1.header\n1353832234\nj4h3g2\n\n\n\n0\n\n\n"
+        );
+        let options = options_at(1353832234).debug_normalized_string(true);
+        assert!(matches!(
+            req.validate_header_with_options(&header, &key, &options),
+            Err(ValidationError::MacMismatch { normalized: Some(n) }) if n == normalized
+        ));
+        assert_eq!(
+            req.validate_header_with_options(&header, &key, &options)
+                .unwrap_err()
+                .to_string(),
+            "Calculated mac does not match; server normalized string: \
+             \"hawk.1.header\\n1353832234\\nj4h3g2\\n\\n\\n\\n0\\n\\n\\n\""
+        );
+        assert_eq!(
+            req.validate_header_with_options(&header, &key, &options_at(1353832234))
+                .unwrap_err()
+                .to_string(),
+            "Calculated mac does not match"
+        );
+    }
+
+    #[test]
+    fn test_normalized_string_matches_generated() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z")
+            .ext("ext")
+            .request();
+        let header = req
+            .make_header_full(&credentials, UNIX_EPOCH + Duration::new(1000, 0), "nonce")
+            .unwrap();
+        let normalized = req.normalized_header_string(&header).unwrap();
+        assert_eq!(
+            normalized.sign(&credentials.key).unwrap(),
+            header.mac.unwrap()
+        );
+
+        let bewit = req
+            .make_bewit(&credentials, UNIX_EPOCH + Duration::from_secs(1000))
+            .unwrap();
+        let normalized = req.normalized_bewit_string(&bewit).unwrap();
+        assert_eq!(&normalized.sign(&credentials.key).unwrap(), bewit.mac());
+    }
+
+    #[test]
+    fn test_validate_bewit_with_options() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z").request();
+        let bewit = req
+            .make_bewit(&credentials, UNIX_EPOCH + Duration::from_secs(1000))
+            .unwrap();
+
+        assert!(req
+            .validate_bewit_with_options(&bewit, &credentials.key, &options_at(999))
+            .is_ok());
+        assert!(matches!(
+            req.validate_bewit_with_options(&bewit, &credentials.key, &options_at(1001)),
+            Err(ValidationError::Expired)
+        ));
+    }
+
+    fn policy_at(secs: u64) -> BewitPolicy {
+        BewitPolicy::new().clock(std::sync::Arc::new(FixedClock(
+            UNIX_EPOCH + Duration::from_secs(secs),
+        )))
+    }
+
+    #[test]
+    fn test_validate_bewit_with_policy() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let exp = UNIX_EPOCH + Duration::from_secs(1000);
+        let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z").request();
+        let bewit = req.make_bewit(&credentials, exp).unwrap();
+
+        assert!(req
+            .validate_bewit_with_policy(&bewit, &credentials.key, &policy_at(999))
+            .is_ok());
+        assert!(matches!(
+            req.validate_bewit_with_policy(&bewit, &credentials.key, &policy_at(1001)),
+            Err(ValidationError::Expired)
+        ));
+
+        // the remaining lifetime is limited
+        let policy = policy_at(100).max_ttl(Duration::from_secs(600));
+        assert!(matches!(
+            req.validate_bewit_with_policy(&bewit, &credentials.key, &policy),
+            Err(ValidationError::TtlTooLong { ttl, max })
+                if ttl == Duration::from_secs(900) && max == Duration::from_secs(600)
+        ));
+        let policy = policy_at(400).max_ttl(Duration::from_secs(600));
+        assert!(req
+            .validate_bewit_with_policy(&bewit, &credentials.key, &policy)
+            .is_ok());
+
+        // a bad MAC is reported before the lifetime
+        let other_key = Key::new("other", crate::SHA256).unwrap();
+        assert!(matches!(
+            req.validate_bewit_with_policy(
+                &bewit,
+                &other_key,
+                &policy_at(100).max_ttl(Duration::ZERO)
+            ),
+            Err(ValidationError::MacMismatch { normalized: None })
+        ));
+
+        // only GET and HEAD are allowed by default
+        let post = RequestBuilder::new("POST", "foo.com", 443, "/x/y/z").request();
+        let bewit = post.make_bewit(&credentials, exp).unwrap();
+        assert!(matches!(
+            post.validate_bewit_with_policy(&bewit, &credentials.key, &policy_at(999)),
+            Err(ValidationError::MethodNotAllowed(method)) if method == "POST"
+        ));
+        let policy = policy_at(999).allowed_methods(["POST"]);
+        assert!(post
+            .validate_bewit_with_policy(&bewit, &credentials.key, &policy)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_bewit_with_policy_ext() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let exp = UNIX_EPOCH + Duration::from_secs(1000);
+        let plain = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z").request();
+        let plain_bewit = plain.make_bewit(&credentials, exp).unwrap();
+        let with_ext = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z")
+            .ext("abcd")
+            .request();
+        let ext_bewit = with_ext.make_bewit(&credentials, exp).unwrap();
+
+        let required = policy_at(999).ext(ExtRequirement::Required);
+        assert!(with_ext
+            .validate_bewit_with_policy(&ext_bewit, &credentials.key, &required)
+            .is_ok());
+        assert!(matches!(
+            plain.validate_bewit_with_policy(&plain_bewit, &credentials.key, &required),
+            Err(ValidationError::MissingExt)
+        ));
+
+        let forbidden = policy_at(999).ext(ExtRequirement::Forbidden);
+        assert!(plain
+            .validate_bewit_with_policy(&plain_bewit, &credentials.key, &forbidden)
+            .is_ok());
+        assert!(matches!(
+            with_ext.validate_bewit_with_policy(&ext_bewit, &credentials.key, &forbidden),
+            Err(ValidationError::UnexpectedExt)
+        ));
+    }
+
+    #[test]
+    fn test_validate_bewit_single_use_and_revoked() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let exp = UNIX_EPOCH + Duration::from_secs(1000);
+        let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z").request();
+        let bewit = req.make_bewit(&credentials, exp).unwrap();
+        let store = std::sync::Arc::new(crate::MemoryBewitStore::new());
+        let revocations = std::sync::Arc::new(crate::MemoryRevocationList::new());
+        let policy = policy_at(500)
+            .single_use(store)
+            .revocations(revocations.clone());
+
+        // an invalid bewit is not recorded as used
+        let other_key = Key::new("other", crate::SHA256).unwrap();
+        assert!(req
+            .validate_bewit_with_policy(&bewit, &other_key, &policy)
+            .is_err());
+
+        assert!(req
+            .validate_bewit_with_policy(&bewit, &credentials.key, &policy)
+            .is_ok());
+        assert!(matches!(
+            req.validate_bewit_with_policy(&bewit, &credentials.key, &policy),
+            Err(ValidationError::BewitReused)
+        ));
+
+        // a different bewit for the same request is unaffected, until revoked
+        let bewit = req
+            .make_bewit(&credentials, exp + Duration::from_secs(1))
+            .unwrap();
+        revocations.revoke("you", UNIX_EPOCH, exp + Duration::from_secs(100));
+        revocations.revoke("me", UNIX_EPOCH, exp);
+        assert!(req
+            .validate_bewit_with_policy(&bewit, &credentials.key, &policy)
+            .is_ok());
+        let bewit = req
+            .make_bewit(&credentials, exp - Duration::from_secs(1))
+            .unwrap();
+        assert!(matches!(
+            req.validate_bewit_with_policy(&bewit, &credentials.key, &policy),
+            Err(ValidationError::Revoked)
+        ));
+    }
+
     fn round_trip_bewit(req: Request, ts: SystemTime, expected: bool) {
         let credentials = Credentials {
             id: "me".to_string(),
@@ -946,6 +1825,103 @@ mod test {
         round_trip_bewit(req, SystemTime::now() + Duration::from_secs(10 * 60), true);
     }
 
+    #[test]
+    fn test_validate_with_keys() {
+        let old_key = Key::new("old", crate::SHA256).unwrap();
+        let new_key = Key::new("new", crate::SHA256).unwrap();
+        let req = RequestBuilder::new("GET", "example.com", 443, "/foo").request();
+        let header = req
+            .make_header_full(
+                &Credentials {
+                    id: "me".to_string(),
+                    key: old_key.clone(),
+                },
+                SystemTime::now(),
+                "nonny",
+            )
+            .unwrap();
+
+        let keys = KeySet::new()
+            .with_key(new_key.clone())
+            .with_key(old_key.clone());
+        let key_match = req
+            .validate_header_with_keys(&header, &keys, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(key_match.index(), 1);
+        assert!(!key_match.is_primary());
+
+        // once the old key's window has passed, it no longer validates
+        let keys = KeySet::new().with_key(new_key).with_key_window(
+            old_key,
+            None,
+            Some(SystemTime::now() - Duration::from_secs(1)),
+        );
+        assert_eq!(
+            req.validate_header_with_keys(&header, &keys, Duration::from_secs(60)),
+            None
+        );
+        assert_eq!(
+            req.validate_header_with_keys(&header, &KeySet::new(), Duration::from_secs(60)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_bewit_with_keys() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z").request();
+        let bewit = req
+            .make_bewit_with_ttl(&credentials, Duration::from_secs(60))
+            .unwrap();
+
+        let keys = KeySet::new().with_key(credentials.key.clone());
+        assert!(req
+            .validate_bewit_with_keys(&bewit, &keys)
+            .unwrap()
+            .is_primary());
+
+        let keys = KeySet::new().with_key(Key::new("other", crate::SHA256).unwrap());
+        assert_eq!(req.validate_bewit_with_keys(&bewit, &keys), None);
+    }
+
+    #[test]
+    fn test_encoded_ext_round_trip() {
+        let credentials = Credentials {
+            id: "me".to_string(),
+            key: Key::new("tok", crate::SHA256).unwrap(),
+        };
+        let values = vec![("msg".to_string(), "\"hello\", \\ w\u{f6}rld".to_string())];
+        let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z")
+            .encode_ext(&crate::UrlEncodedExt, &values)
+            .unwrap()
+            .request();
+
+        let header = req.make_header(&credentials).unwrap();
+        let header = Header::from_str(&header.to_string()).unwrap();
+        assert_eq!(
+            header.decode_ext(&crate::UrlEncodedExt).unwrap(),
+            Some(values.clone())
+        );
+        assert!(req.validate_header(&header, &credentials.key, Duration::from_secs(60)));
+
+        let bewit = req
+            .make_bewit_with_ttl(&credentials, Duration::from_secs(60))
+            .unwrap();
+        let bewit = Bewit::from_str(&bewit.to_str()).unwrap();
+        assert_eq!(
+            bewit.decode_ext(&crate::UrlEncodedExt).unwrap(),
+            Some(values)
+        );
+        assert!(req.validate_bewit(&bewit, &credentials.key));
+
+        assert!(RequestBuilder::new("GET", "foo.com", 443, "/")
+            .encode_ext(&crate::RawExt, &"\"".to_string())
+            .is_err());
+    }
+
     #[test]
     fn test_validate_bewit_expired() {
         let req = RequestBuilder::new("GET", "foo.com", 443, "/x/y/z").request();
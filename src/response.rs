@@ -1,7 +1,10 @@
 use crate::credentials::Key;
 use crate::error::*;
-use crate::header::Header;
-use crate::mac::{Mac, MacType};
+use crate::ext::ExtCodec;
+use crate::header::{Header, HeaderBuilder};
+use crate::mac::{Mac, MacType, NormalizedFields, NormalizedString};
+use crate::payload::PayloadHash;
+use std::borrow::Cow;
 
 /// A Response represents a response from an HTTP server.
 ///
@@ -15,13 +18,13 @@ use crate::mac::{Mac, MacType};
 /// See the documentation in the crate root for examples.
 #[derive(Debug, Clone)]
 pub struct Response<'a> {
-    method: &'a str,
-    host: &'a str,
+    method: Cow<'a, str>,
+    host: Cow<'a, str>,
     port: u16,
-    path: &'a str,
-    req_header: &'a Header,
-    hash: Option<&'a [u8]>,
-    ext: Option<&'a str>,
+    path: Cow<'a, str>,
+    req_header: Cow<'a, Header>,
+    hash: Option<Cow<'a, PayloadHash>>,
+    ext: Option<Cow<'a, str>>,
 }
 
 impl<'a> Response<'a> {
@@ -34,25 +37,23 @@ impl<'a> Response<'a> {
             key,
             ts,
             nonce,
-            self.method,
-            self.host,
+            &self.method,
+            &self.host,
             self.port,
-            self.path,
-            self.hash,
-            self.ext,
+            &self.path,
+            self.hash.as_deref().map(PayloadHash::as_bytes),
+            self.ext.as_deref(),
         )?;
 
         // Per JS implementation, the Server-Authorization header includes only mac, hash, and ext
-        Header::new(
-            None,
-            None,
-            None,
-            Some(mac),
-            self.ext.map(|v| v.to_string()),
-            self.hash.map(|v| v.to_vec()),
-            None,
-            None,
-        )
+        let mut builder = HeaderBuilder::server_authorization(mac);
+        if let Some(ref ext) = self.ext {
+            builder = builder.ext(ext.as_ref());
+        }
+        if let Some(ref hash) = self.hash {
+            builder = builder.hash(hash.as_ref().clone());
+        }
+        builder.build()
     }
 
     /// Validate a Server-Authorization header.
@@ -61,54 +62,29 @@ impl<'a> Response<'a> {
     /// checks that one was provided from the server and that it, too, matches.
     pub fn validate_header(&self, response_header: &Header, key: &Key) -> bool {
         // extract required fields, returning early if they are not present
-        let ts = match self.req_header.ts {
-            Some(ts) => ts,
-            None => {
-                return false;
-            }
-        };
-        let nonce = match self.req_header.nonce {
-            Some(ref nonce) => nonce,
-            None => {
-                return false;
-            }
-        };
         let header_mac = match response_header.mac {
             Some(ref mac) => mac,
             None => {
                 return false;
             }
         };
-        let header_ext = response_header.ext.as_ref().map(|ext| &ext[..]);
-        let header_hash = response_header.hash.as_ref().map(|hash| &hash[..]);
+        let header_hash = response_header.hash.as_ref();
 
         // first verify the MAC
-        match Mac::new(
-            MacType::Response,
-            key,
-            ts,
-            nonce,
-            self.method,
-            self.host,
-            self.port,
-            self.path,
-            header_hash,
-            header_ext,
-        ) {
-            Ok(calculated_mac) => {
-                if &calculated_mac != header_mac {
-                    return false;
-                }
-            }
-            Err(_) => {
+        match self
+            .header_fields(response_header)
+            .and_then(|fields| fields.matches(key, header_mac))
+        {
+            Ok(true) => {}
+            Ok(false) | Err(_) => {
                 return false;
             }
         };
 
         // ..then the hashes
-        if let Some(local_hash) = self.hash {
+        if let Some(ref local_hash) = self.hash {
             if let Some(server_hash) = header_hash {
-                if local_hash != server_hash {
+                if local_hash.as_ref() != server_hash {
                     return false;
                 }
             } else {
@@ -121,6 +97,45 @@ impl<'a> Response<'a> {
 
         true
     }
+
+    /// Get the normalized string that the MAC in the given `Server-Authorization` header signs,
+    /// if the header was made for this response.
+    ///
+    /// This is useful for debugging MAC mismatches.  The request header must have `ts` and
+    /// `nonce` attributes.
+    pub fn normalized_header_string(&self, response_header: &Header) -> Result<NormalizedString> {
+        Ok(self.header_fields(response_header)?.to_normalized_string())
+    }
+
+    fn header_fields<'h>(&'h self, response_header: &'h Header) -> Result<NormalizedFields<'h>> {
+        let ts = self.req_header.ts.ok_or(Error::MissingTs)?;
+        let nonce = self.req_header.nonce.as_ref().ok_or(Error::MissingNonce)?;
+        Ok(NormalizedFields::new(
+            MacType::Response,
+            ts,
+            nonce,
+            &self.method,
+            &self.host,
+            self.port,
+            &self.path,
+            response_header.hash.as_ref().map(PayloadHash::as_bytes),
+            response_header.ext.as_deref(),
+        ))
+    }
+
+    /// Convert this response into one that owns all of its fields, including the request
+    /// header.
+    pub fn into_owned(self) -> Response<'static> {
+        Response {
+            method: Cow::Owned(self.method.into_owned()),
+            host: Cow::Owned(self.host.into_owned()),
+            port: self.port,
+            path: Cow::Owned(self.path.into_owned()),
+            req_header: Cow::Owned(self.req_header.into_owned()),
+            hash: self.hash.map(|v| Cow::Owned(v.into_owned())),
+            ext: self.ext.map(|v| Cow::Owned(v.into_owned())),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -130,19 +145,26 @@ impl<'a> ResponseBuilder<'a> {
     /// Generate a new Response from a request header.
     ///
     /// This is more commonly accessed through `Request::make_response`.
-    pub fn from_request_header(
+    ///
+    /// The method, host, and path may be borrowed (`&str`) or owned (`String`).
+    pub fn from_request_header<M, H, P>(
         req_header: &'a Header,
-        method: &'a str,
-        host: &'a str,
+        method: M,
+        host: H,
         port: u16,
-        path: &'a str,
-    ) -> Self {
+        path: P,
+    ) -> Self
+    where
+        M: Into<Cow<'a, str>>,
+        H: Into<Cow<'a, str>>,
+        P: Into<Cow<'a, str>>,
+    {
         ResponseBuilder(Response {
-            method,
-            host,
+            method: method.into(),
+            host: host.into(),
             port,
-            path,
-            req_header,
+            path: path.into(),
+            req_header: Cow::Borrowed(req_header),
             hash: None,
             ext: None,
         })
@@ -151,23 +173,35 @@ impl<'a> ResponseBuilder<'a> {
     /// Set the content hash for the response.
     ///
     /// This should always be calculated from the response payload, not copied from a header.
-    pub fn hash<H: Into<Option<&'a [u8]>>>(mut self, hash: H) -> Self {
-        self.0.hash = hash.into();
+    pub fn hash<H: Into<Cow<'a, PayloadHash>>>(mut self, hash: H) -> Self {
+        self.0.hash = Some(hash.into());
         self
     }
 
     /// Set the `ext` Hawk property for the response.
     ///
     /// This need only be set on the server; it is ignored in validating responses on the client.
-    pub fn ext<S: Into<Option<&'a str>>>(mut self, ext: S) -> Self {
-        self.0.ext = ext.into();
+    pub fn ext<S: Into<Cow<'a, str>>>(mut self, ext: S) -> Self {
+        self.0.ext = Some(ext.into());
         self
     }
 
+    /// Set the `ext` Hawk property for the response to the given value, encoded with the given
+    /// codec.
+    pub fn encode_ext<C: ExtCodec>(mut self, codec: &C, value: &C::Value) -> Result<Self> {
+        self.0.ext = Some(Cow::Owned(codec.encode(value)?));
+        Ok(self)
+    }
+
     /// Get the response from this builder
     pub fn response(self) -> Response<'a> {
         self.0
     }
+
+    /// Convert this builder into one that owns all of its fields.  See `Response::into_owned`.
+    pub fn into_owned(self) -> ResponseBuilder<'static> {
+        ResponseBuilder(self.0.into_owned())
+    }
 }
 
 #[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
@@ -176,8 +210,13 @@ mod test {
     use crate::credentials::Key;
     use crate::header::Header;
     use crate::mac::Mac;
+    use crate::payload::PayloadHash;
     use std::time::{Duration, UNIX_EPOCH};
 
+    fn test_hash(byte: u8) -> PayloadHash {
+        PayloadHash::new(crate::SHA256, vec![byte; 32]).unwrap()
+    }
+
     fn make_req_header() -> Header {
         Header::new(
             None,
@@ -192,6 +231,21 @@ mod test {
         .unwrap()
     }
 
+    #[test]
+    fn test_into_owned() {
+        let key = Key::new(vec![99u8; 32], crate::SHA256).unwrap();
+        let resp = {
+            let req_header = make_req_header();
+            let path = String::from("/a/b");
+            ResponseBuilder::from_request_header(&req_header, "POST", "localhost", 9988, &path)
+                .ext("ext")
+                .into_owned()
+                .response()
+        };
+        let server_header = resp.make_header(&key).unwrap();
+        assert!(resp.validate_header(&server_header, &key));
+    }
+
     #[test]
     fn test_validation_no_hash() {
         let req_header = make_req_header();
@@ -225,8 +279,8 @@ mod test {
             ResponseBuilder::from_request_header(&req_header, "POST", "localhost", 9988, "/a/b")
                 .response();
         let mac: Mac = Mac::from(vec![
-            33, 147, 159, 211, 184, 194, 189, 74, 53, 229, 241, 161, 215, 145, 22, 34, 206, 207,
-            242, 100, 33, 193, 36, 96, 149, 133, 180, 4, 132, 87, 207, 238,
+            170, 171, 36, 117, 167, 67, 63, 27, 235, 62, 153, 154, 5, 87, 63, 144, 254, 40, 186,
+            189, 25, 249, 241, 179, 241, 36, 251, 5, 139, 25, 56, 88,
         ]);
         let server_header = Header::new(
             None,
@@ -234,7 +288,7 @@ mod test {
             None,
             Some(mac),
             Some("server-ext"),
-            Some(vec![1, 2, 3, 4]),
+            Some(test_hash(1)),
             None,
             None,
         )
@@ -246,10 +300,10 @@ mod test {
     fn test_validation_hash_required_but_not_given() {
         // When Response.hash is called, but no hash is in the hader, validation fails.
         let req_header = make_req_header();
-        let hash = vec![1, 2, 3, 4];
+        let hash = test_hash(1);
         let resp =
             ResponseBuilder::from_request_header(&req_header, "POST", "localhost", 9988, "/a/b")
-                .hash(&hash[..])
+                .hash(&hash)
                 .response();
         let mac: Mac = Mac::from(vec![
             48, 133, 228, 163, 224, 197, 222, 77, 117, 81, 143, 73, 71, 120, 68, 238, 228, 40, 55,
@@ -274,14 +328,14 @@ mod test {
         // When a hash is provided in the response header and the Response.hash method is called,
         // the two must match
         let req_header = make_req_header();
-        let hash = vec![1, 2, 3, 4];
+        let hash = test_hash(1);
         let resp =
             ResponseBuilder::from_request_header(&req_header, "POST", "localhost", 9988, "/a/b")
-                .hash(&hash[..])
+                .hash(&hash)
                 .response();
         let mac: Mac = Mac::from(vec![
-            33, 147, 159, 211, 184, 194, 189, 74, 53, 229, 241, 161, 215, 145, 22, 34, 206, 207,
-            242, 100, 33, 193, 36, 96, 149, 133, 180, 4, 132, 87, 207, 238,
+            170, 171, 36, 117, 167, 67, 63, 27, 235, 62, 153, 154, 5, 87, 63, 144, 254, 40, 186,
+            189, 25, 249, 241, 179, 241, 36, 251, 5, 139, 25, 56, 88,
         ]);
         let server_header = Header::new(
             None,
@@ -289,7 +343,7 @@ mod test {
             None,
             Some(mac),
             Some("server-ext"),
-            Some(vec![1, 2, 3, 4]),
+            Some(test_hash(1)),
             None,
             None,
         )
@@ -297,10 +351,10 @@ mod test {
         assert!(resp.validate_header(&server_header, &Key::new("tok", crate::SHA256).unwrap()));
 
         // a different supplied hash won't match..
-        let hash = vec![99, 99, 99, 99];
+        let hash = test_hash(99);
         let resp =
             ResponseBuilder::from_request_header(&req_header, "POST", "localhost", 9988, "/a/b")
-                .hash(&hash[..])
+                .hash(&hash)
                 .response();
         assert!(!resp.validate_header(&server_header, &Key::new("tok", crate::SHA256).unwrap()));
     }
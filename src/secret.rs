@@ -0,0 +1,137 @@
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Secret key material.
+///
+/// The bytes are overwritten with zeroes when the value is dropped, and the `Debug`
+/// implementation does not reveal them, so values of this type can safely appear in structured
+/// logs.  Comparisons between `SecretBytes` values are constant-time.
+///
+/// Anything that can be converted to a `Vec<u8>` without copying (such as `Vec<u8>` or `String`)
+/// can be converted to `SecretBytes` without leaving a copy of the secret behind.  Conversions
+/// from borrowed data make a copy, and it is up to the caller to clear the original.
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Create a new secret from the given bytes.
+    pub fn new<B: Into<Vec<u8>>>(bytes: B) -> Self {
+        SecretBytes(bytes.into())
+    }
+
+    /// Generate a new secret of `len` random bytes, using the configured `Cryptographer`.
+    pub fn generate(len: usize) -> crate::Result<Self> {
+        let mut secret = SecretBytes(vec![0u8; len]);
+        crate::crypto::rand_bytes(&mut secret.0)?;
+        Ok(secret)
+    }
+
+    /// Get the secret bytes.  Take care not to copy them anywhere they will not be cleared.
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0[..]
+    }
+
+    pub(crate) fn expose_secret_mut(&mut self) -> &mut [u8] {
+        &mut self.0[..]
+    }
+
+    /// Get the length of the secret, in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Determine whether the secret is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.expose_secret()
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl From<String> for SecretBytes {
+    fn from(s: String) -> Self {
+        SecretBytes(s.into_bytes())
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        SecretBytes(bytes.to_vec())
+    }
+}
+
+impl From<&str> for SecretBytes {
+    fn from(s: &str) -> Self {
+        SecretBytes(s.as_bytes().to_vec())
+    }
+}
+
+impl Zeroize for SecretBytes {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretBytes {}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &SecretBytes) -> bool {
+        crate::crypto::constant_time_compare(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretBytes(<redacted>)")
+    }
+}
+
+#[cfg(all(test, any(feature = "use_ring", feature = "use_openssl")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacted() {
+        let secret = SecretBytes::from("hunter2");
+        assert_eq!(format!("{secret:?}"), "SecretBytes(<redacted>)");
+    }
+
+    #[test]
+    fn test_zeroize() {
+        let mut secret = SecretBytes::from(vec![1u8, 2, 3]);
+        secret.zeroize();
+        assert!(secret.is_empty());
+    }
+
+    #[test]
+    fn test_generate() {
+        let secret = SecretBytes::generate(20).unwrap();
+        assert_eq!(secret.len(), 20);
+        assert_ne!(secret, SecretBytes::from(vec![0u8; 20]));
+    }
+
+    #[test]
+    fn test_eq() {
+        assert_eq!(SecretBytes::from("abc"), SecretBytes::from(&b"abc"[..]));
+        assert_ne!(SecretBytes::from("abc"), SecretBytes::from("abd"));
+        assert_ne!(SecretBytes::from("abc"), SecretBytes::from("abcd"));
+    }
+}
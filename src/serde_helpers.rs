@@ -0,0 +1,32 @@
+//! Helpers for use with `serde`'s `with` attribute, available with the `serde` feature.
+
+/// Serialize a [`Bewit`](crate::Bewit) as its encoded string, as it would appear in a URL,
+/// rather than as a structure of its fields.
+///
+/// # Examples
+///
+/// ```
+/// # #[derive(serde::Serialize, serde::Deserialize)]
+/// struct SignedLink {
+///     url: String,
+///     #[serde(with = "hawk::serde_helpers::bewit_string")]
+///     bewit: hawk::Bewit<'static>,
+/// }
+/// ```
+pub mod bewit_string {
+    use crate::Bewit;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::borrow::Cow;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(bewit: &Bewit, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bewit.to_str())
+    }
+
+    pub fn deserialize<'de, 'a, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Bewit<'a>, D::Error> {
+        let encoded = <Cow<'de, str>>::deserialize(deserializer)?;
+        Bewit::from_str(&encoded).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,746 @@
+use crate::credentials::Key;
+use crate::error::ValidationError;
+use crate::key_set::{KeyMatch, KeySet};
+use crate::payload::{PayloadHash, PayloadHasher};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// How a server treats the payload hash when validating a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PayloadHashRequirement {
+    /// The header must contain a hash, and it must match the hash given to the `Request`.  If
+    /// the `Request` has no hash, validation fails.
+    Required,
+    /// If a hash was given to the `Request`, the header must contain a matching hash.  This is
+    /// the behavior of `Request::validate_header`.
+    #[default]
+    VerifyIfPresent,
+    /// The payload hash is not compared.  Any hash in the header is still covered by the MAC.
+    Ignore,
+}
+
+/// A source of the current time, used when validating timestamps and expiration times.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// A `Clock` that uses the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A record of nonces that have been used, so that replayed requests can be rejected.
+pub trait NonceStore: Send + Sync {
+    /// Record the use of `nonce` by `id` in a request with timestamp `ts`, returning false if
+    /// the nonce has already been used.  `now` is the current time, according to the `Clock` of
+    /// the `ValidationOptions`.
+    fn check_and_insert(&self, id: &str, nonce: &str, ts: SystemTime, now: SystemTime) -> bool;
+}
+
+/// A `NonceStore` that keeps nonces in memory.
+///
+/// Nonces are retained until the given duration after the timestamp of the request in which they
+/// were used has passed, after which they are forgotten as newer requests arrive.  The retention
+/// should be at least the past skew allowed by the `ValidationOptions`, as requests older than
+/// that are rejected anyway.
+#[derive(Debug)]
+pub struct MemoryNonceStore {
+    retention: Duration,
+    state: Mutex<NonceState>,
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    seen: HashSet<(String, String)>,
+    /// The same entries as `seen`, ordered by the time at which they can be forgotten.
+    expiry: BTreeSet<(SystemTime, String, String)>,
+}
+
+impl MemoryNonceStore {
+    pub fn new(retention: Duration) -> Self {
+        MemoryNonceStore {
+            retention,
+            state: Mutex::new(NonceState::default()),
+        }
+    }
+}
+
+impl NonceStore for MemoryNonceStore {
+    fn check_and_insert(&self, id: &str, nonce: &str, ts: SystemTime, now: SystemTime) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some((expires, _, _)) = state.expiry.first() {
+            if *expires >= now {
+                break;
+            }
+            let (_, id, nonce) = state.expiry.pop_first().unwrap();
+            state.seen.remove(&(id, nonce));
+        }
+
+        let key = (id.to_string(), nonce.to_string());
+        if state.seen.contains(&key) {
+            return false;
+        }
+        let expires = ts.checked_add(self.retention).unwrap_or(ts);
+        state.expiry.insert((expires, key.0.clone(), key.1.clone()));
+        state.seen.insert(key);
+        true
+    }
+}
+
+/// A record of bewits that have been used, so that single-use bewits can be enforced.
+pub trait BewitStore: Send + Sync {
+    /// Record the use of the bewit with the given `mac`, which expires at `exp`, returning false
+    /// if it has already been used.  Bewits that expired before `now` need not be retained.
+    fn check_and_insert(&self, mac: &[u8], exp: SystemTime, now: SystemTime) -> bool;
+}
+
+/// A `BewitStore` that keeps the MACs of used bewits in memory.
+///
+/// Each MAC is retained until its bewit expires, after which it is forgotten as newer bewits
+/// are used.  The memory required thus depends on the bewits' lifetime, which can be limited
+/// with `BewitPolicy::max_ttl`.
+#[derive(Debug, Default)]
+pub struct MemoryBewitStore {
+    state: Mutex<BewitState>,
+}
+
+#[derive(Debug, Default)]
+struct BewitState {
+    used: HashSet<Vec<u8>>,
+    /// The same MACs as `used`, ordered by the expiration time of their bewits.
+    expiry: BTreeSet<(SystemTime, Vec<u8>)>,
+}
+
+impl MemoryBewitStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        MemoryBewitStore::default()
+    }
+}
+
+impl BewitStore for MemoryBewitStore {
+    fn check_and_insert(&self, mac: &[u8], exp: SystemTime, now: SystemTime) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some((expires, _)) = state.expiry.first() {
+            if *expires >= now {
+                break;
+            }
+            let (_, mac) = state.expiry.pop_first().unwrap();
+            state.used.remove(&mac);
+        }
+
+        if state.used.contains(mac) {
+            return false;
+        }
+        state.expiry.insert((exp, mac.to_vec()));
+        state.used.insert(mac.to_vec());
+        true
+    }
+}
+
+/// A list of revoked bewits.
+///
+/// Bewits carry no issue time, so revocations are expressed in terms of the bewit's `id` and
+/// expiration time.  When the lifetime of bewits is limited to `max_ttl`, every bewit issued
+/// before time `t` expires before `t + max_ttl`.
+pub trait RevocationList: Send + Sync {
+    /// Determine whether the bewit for `id` expiring at `exp` has been revoked.  Revocations
+    /// covering only bewits that expired before `now` need not be retained.
+    fn is_revoked(&self, id: &str, exp: SystemTime, now: SystemTime) -> bool;
+}
+
+/// A `RevocationList` that keeps revocations in memory.
+///
+/// Checking a revocation does not modify the list, so revocations are kept until `purge` is
+/// called.
+#[derive(Debug, Default)]
+pub struct MemoryRevocationList {
+    revoked: RwLock<HashMap<String, Vec<(SystemTime, SystemTime)>>>,
+}
+
+impl MemoryRevocationList {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        MemoryRevocationList::default()
+    }
+
+    /// Revoke the bewits for `id` that expire between `from` and `until`, inclusive.
+    ///
+    /// To revoke all of the bewits issued to `id` so far, when their lifetime is limited to
+    /// `max_ttl`, use `revoke(id, UNIX_EPOCH, now + max_ttl)`.  Note that bewits issued later,
+    /// but expiring within the window, are also revoked.
+    pub fn revoke<S: Into<String>>(&self, id: S, from: SystemTime, until: SystemTime) {
+        let mut revoked = self.revoked.write().unwrap_or_else(|e| e.into_inner());
+        revoked.entry(id.into()).or_default().push((from, until));
+    }
+
+    /// Forget revocations covering only bewits that expired before `now`.  Such bewits are
+    /// rejected as expired anyway, so this can be called periodically to limit the size of the
+    /// list.
+    pub fn purge(&self, now: SystemTime) {
+        let mut revoked = self.revoked.write().unwrap_or_else(|e| e.into_inner());
+        revoked.retain(|_, windows| {
+            windows.retain(|(_, until)| *until >= now);
+            !windows.is_empty()
+        });
+    }
+}
+
+impl RevocationList for MemoryRevocationList {
+    fn is_revoked(&self, id: &str, exp: SystemTime, _now: SystemTime) -> bool {
+        let revoked = self.revoked.read().unwrap_or_else(|e| e.into_inner());
+        revoked.get(id).is_some_and(|windows| {
+            windows
+                .iter()
+                .any(|(from, until)| *from <= exp && exp <= *until)
+        })
+    }
+}
+
+/// The options shared by `ValidationOptions` and `BewitPolicy`.
+#[derive(Clone)]
+pub(crate) struct CommonOptions {
+    pub(crate) allowed_methods: Option<Vec<String>>,
+    pub(crate) clock: Option<Arc<dyn Clock>>,
+    pub(crate) debug_normalized_string: bool,
+}
+
+impl CommonOptions {
+    fn new(allowed_methods: Option<Vec<String>>) -> Self {
+        CommonOptions {
+            allowed_methods,
+            clock: None,
+            debug_normalized_string: false,
+        }
+    }
+
+    fn set_allowed_methods<I, S>(&mut self, methods: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_methods = Some(methods.into_iter().map(Into::into).collect());
+    }
+
+    pub(crate) fn now(&self) -> SystemTime {
+        match self.clock {
+            Some(ref clock) => clock.now(),
+            None => SystemTime::now(),
+        }
+    }
+
+    pub(crate) fn method_allowed(&self, method: &str) -> bool {
+        match self.allowed_methods {
+            Some(ref methods) => methods.iter().any(|m| m == method),
+            None => true,
+        }
+    }
+
+    fn debug_fields(&self, f: &mut fmt::DebugStruct) {
+        f.field("allowed_methods", &self.allowed_methods)
+            .field("clock", &self.clock.is_some())
+            .field("debug_normalized_string", &self.debug_normalized_string);
+    }
+}
+
+/// Policy for validating requests on the server.
+///
+/// The default options match the behavior of `Request::validate_header` with a skew of one
+/// minute: the payload hash is verified if one is given to the `Request`, timestamps may be up
+/// to a minute in the past or future, `app` and `dlg` are not checked, any method is allowed,
+/// nonces are not checked, and the system clock is used.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{PayloadHashRequirement, ValidationOptions, MemoryNonceStore};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let options = ValidationOptions::new()
+///     .payload_hash(PayloadHashRequirement::Required)
+///     .max_past_skew(Duration::from_secs(60))
+///     .max_future_skew(Duration::from_secs(5))
+///     .allowed_methods(["GET", "POST"])
+///     .nonce_store(Arc::new(MemoryNonceStore::new(Duration::from_secs(65))));
+/// ```
+#[derive(Clone)]
+pub struct ValidationOptions {
+    pub(crate) payload_hash: PayloadHashRequirement,
+    pub(crate) max_past_skew: Duration,
+    pub(crate) max_future_skew: Duration,
+    pub(crate) require_app_match: bool,
+    pub(crate) require_dlg_match: bool,
+    pub(crate) nonce_store: Option<Arc<dyn NonceStore>>,
+    pub(crate) common: CommonOptions,
+}
+
+const DEFAULT_SKEW: Duration = Duration::from_secs(60);
+
+impl ValidationOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        ValidationOptions {
+            payload_hash: PayloadHashRequirement::default(),
+            max_past_skew: DEFAULT_SKEW,
+            max_future_skew: DEFAULT_SKEW,
+            require_app_match: false,
+            require_dlg_match: false,
+            nonce_store: None,
+            common: CommonOptions::new(None),
+        }
+    }
+
+    /// Set how the payload hash is treated.
+    pub fn payload_hash(mut self, requirement: PayloadHashRequirement) -> Self {
+        self.payload_hash = requirement;
+        self
+    }
+
+    /// Set both the maximum past and future skew of the header timestamp.
+    pub fn ts_skew(self, skew: Duration) -> Self {
+        self.max_past_skew(skew).max_future_skew(skew)
+    }
+
+    /// Set how far in the past the header timestamp may be.
+    pub fn max_past_skew(mut self, skew: Duration) -> Self {
+        self.max_past_skew = skew;
+        self
+    }
+
+    /// Set how far in the future the header timestamp may be.
+    pub fn max_future_skew(mut self, skew: Duration) -> Self {
+        self.max_future_skew = skew;
+        self
+    }
+
+    /// Require the header's `app` to match that given to the `Request`.  If the `Request` has
+    /// no `app`, the header must not have one either.
+    pub fn require_app_match(mut self, require: bool) -> Self {
+        self.require_app_match = require;
+        self
+    }
+
+    /// Require the header's `dlg` to match that given to the `Request`.  If the `Request` has
+    /// no `dlg`, the header must not have one either.
+    pub fn require_dlg_match(mut self, require: bool) -> Self {
+        self.require_dlg_match = require;
+        self
+    }
+
+    /// Only allow requests with one of the given methods.  Methods are compared
+    /// case-sensitively.
+    pub fn allowed_methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.common.set_allowed_methods(methods);
+        self
+    }
+
+    /// Reject requests whose nonce has already been used, according to the given store.
+    pub fn nonce_store(mut self, store: Arc<dyn NonceStore>) -> Self {
+        self.nonce_store = Some(store);
+        self
+    }
+
+    /// Use the given clock, rather than the system time.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.common.clock = Some(clock);
+        self
+    }
+
+    /// Include the normalized string calculated by the server in
+    /// `ValidationError::MacMismatch`, to help debug interoperability problems.
+    ///
+    /// The normalized string contains the request's components, such as its path, nonce and
+    /// `ext`, but not the key.  Consider whether it is safe to log before enabling this in
+    /// production, and never send it back to the client.
+    pub fn debug_normalized_string(mut self, debug: bool) -> Self {
+        self.common.debug_normalized_string = debug;
+        self
+    }
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions::new()
+    }
+}
+
+impl fmt::Debug for ValidationOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = f.debug_struct("ValidationOptions");
+        f.field("payload_hash", &self.payload_hash)
+            .field("max_past_skew", &self.max_past_skew)
+            .field("max_future_skew", &self.max_future_skew)
+            .field("require_app_match", &self.require_app_match)
+            .field("require_dlg_match", &self.require_dlg_match)
+            .field("nonce_store", &self.nonce_store.is_some());
+        self.common.debug_fields(&mut f);
+        f.finish()
+    }
+}
+
+/// Whether a bewit may carry an `ext` value, as required by a `BewitPolicy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ExtRequirement {
+    /// The bewit may or may not have an `ext`.
+    #[default]
+    Optional,
+    /// The bewit must have an `ext`.
+    Required,
+    /// The bewit must not have an `ext`.
+    Forbidden,
+}
+
+/// Policy for validating bewits on the server.
+///
+/// Bewits are bearer credentials embedded in URLs, and their expiration time is chosen by the
+/// client that made them, so a server will usually want to limit how long a bewit may remain
+/// valid.  Since a bewit carries no issue time, this is done by limiting its remaining lifetime:
+/// the time between now and its expiration.
+///
+/// The default policy allows only `GET` and `HEAD` requests, as the JS Hawk implementation does,
+/// places no limit on the remaining lifetime, allows but does not require an `ext`, allows
+/// bewits to be used more than once, checks no revocations, and uses the system clock.
+///
+/// # Examples
+///
+/// ```
+/// use hawk::{BewitPolicy, ExtRequirement};
+/// use std::time::Duration;
+///
+/// let policy = BewitPolicy::new()
+///     .max_ttl(Duration::from_secs(15 * 60))
+///     .allowed_methods(["GET"])
+///     .ext(ExtRequirement::Forbidden);
+/// ```
+#[derive(Clone)]
+pub struct BewitPolicy {
+    pub(crate) max_ttl: Option<Duration>,
+    pub(crate) ext: ExtRequirement,
+    pub(crate) bewit_store: Option<Arc<dyn BewitStore>>,
+    pub(crate) revocations: Option<Arc<dyn RevocationList>>,
+    pub(crate) common: CommonOptions,
+}
+
+impl BewitPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        BewitPolicy {
+            max_ttl: None,
+            ext: ExtRequirement::default(),
+            bewit_store: None,
+            revocations: None,
+            common: CommonOptions::new(Some(vec!["GET".to_string(), "HEAD".to_string()])),
+        }
+    }
+
+    /// Reject bewits that expire more than `max_ttl` after the current time.
+    pub fn max_ttl(mut self, max_ttl: Duration) -> Self {
+        self.max_ttl = Some(max_ttl);
+        self
+    }
+
+    /// Only allow requests with one of the given methods, in place of `GET` and `HEAD`.  Methods
+    /// are compared case-sensitively.
+    pub fn allowed_methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.common.set_allowed_methods(methods);
+        self
+    }
+
+    /// Set whether the bewit may, must or must not have an `ext`.
+    pub fn ext(mut self, requirement: ExtRequirement) -> Self {
+        self.ext = requirement;
+        self
+    }
+
+    /// Allow each bewit to be used only once, recording used bewits in the given store.
+    pub fn single_use(mut self, store: Arc<dyn BewitStore>) -> Self {
+        self.bewit_store = Some(store);
+        self
+    }
+
+    /// Reject bewits that have been revoked, according to the given list.
+    pub fn revocations(mut self, revocations: Arc<dyn RevocationList>) -> Self {
+        self.revocations = Some(revocations);
+        self
+    }
+
+    /// Use the given clock, rather than the system time.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.common.clock = Some(clock);
+        self
+    }
+
+    /// Include the normalized string calculated by the server in
+    /// `ValidationError::MacMismatch`.  See `ValidationOptions::debug_normalized_string`.
+    pub fn debug_normalized_string(mut self, debug: bool) -> Self {
+        self.common.debug_normalized_string = debug;
+        self
+    }
+
+    /// Check the remaining lifetime and the `ext` of a bewit, whose MAC has already been
+    /// verified, against this policy.
+    pub(crate) fn check(
+        &self,
+        exp: SystemTime,
+        ext: Option<&str>,
+        now: SystemTime,
+    ) -> Result<(), ValidationError> {
+        if let Some(max) = self.max_ttl {
+            if let Ok(ttl) = exp.duration_since(now) {
+                if ttl > max {
+                    return Err(ValidationError::TtlTooLong { ttl, max });
+                }
+            }
+        }
+        match (self.ext, ext) {
+            (ExtRequirement::Required, None) => Err(ValidationError::MissingExt),
+            (ExtRequirement::Forbidden, Some(_)) => Err(ValidationError::UnexpectedExt),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for BewitPolicy {
+    fn default() -> Self {
+        BewitPolicy::new()
+    }
+}
+
+impl fmt::Debug for BewitPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = f.debug_struct("BewitPolicy");
+        f.field("max_ttl", &self.max_ttl)
+            .field("ext", &self.ext)
+            .field("bewit_store", &self.bewit_store.is_some())
+            .field("revocations", &self.revocations.is_some());
+        self.common.debug_fields(&mut f);
+        f.finish()
+    }
+}
+
+/// The keys against which a request is validated: either a single [`Key`] or a [`KeySet`].
+pub trait ValidationKeys {
+    /// Iterate over the keys that are active at the given time, with their indexes.
+    fn active_keys(&self, now: SystemTime) -> impl Iterator<Item = (usize, &Key)>;
+}
+
+impl ValidationKeys for Key {
+    fn active_keys(&self, _now: SystemTime) -> impl Iterator<Item = (usize, &Key)> {
+        std::iter::once((0, self))
+    }
+}
+
+impl ValidationKeys for KeySet {
+    fn active_keys(&self, now: SystemTime) -> impl Iterator<Item = (usize, &Key)> {
+        self.active_at(now)
+    }
+}
+
+/// Check a locally-computed payload hash against that claimed by a header.
+pub(crate) fn check_payload_hash(
+    requirement: PayloadHashRequirement,
+    local_hash: Option<&PayloadHash>,
+    header_hash: Option<&PayloadHash>,
+) -> Result<(), ValidationError> {
+    match (requirement, local_hash, header_hash) {
+        (PayloadHashRequirement::Ignore, _, _) => Ok(()),
+        (PayloadHashRequirement::Required, None, _) => Err(ValidationError::HashNotComputed),
+        (PayloadHashRequirement::VerifyIfPresent, None, _) => Ok(()),
+        (_, Some(_), None) => Err(ValidationError::MissingHash),
+        (_, Some(local_hash), Some(header_hash)) => {
+            if local_hash == header_hash {
+                Ok(())
+            } else {
+                Err(ValidationError::HashMismatch)
+            }
+        }
+    }
+}
+
+/// A request whose header has been authenticated, but whose payload has not yet been verified.
+///
+/// This is returned from `Request::authenticate_header`, and holds the payload hash claimed by
+/// the header.  Once the request body has been read, complete validation with `verify_hasher` or
+/// `verify_hash`, or with `verify_empty` if no body is expected.  The payload is checked
+/// according to the `PayloadHashRequirement` of the `ValidationOptions` used to authenticate the
+/// header.
+#[must_use = "the payload has not been verified"]
+#[derive(Debug)]
+pub struct PendingPayload {
+    key_match: KeyMatch,
+    claimed_hash: Option<PayloadHash>,
+    requirement: PayloadHashRequirement,
+}
+
+impl PendingPayload {
+    pub(crate) fn new(
+        key_match: KeyMatch,
+        claimed_hash: Option<PayloadHash>,
+        requirement: PayloadHashRequirement,
+    ) -> Self {
+        PendingPayload {
+            key_match,
+            claimed_hash,
+            requirement,
+        }
+    }
+
+    /// Get the key that authenticated the header.
+    pub fn key_match(&self) -> KeyMatch {
+        self.key_match
+    }
+
+    /// Get the payload hash claimed by the header, if any.
+    pub fn claimed_hash(&self) -> Option<&PayloadHash> {
+        self.claimed_hash.as_ref()
+    }
+
+    /// Complete validation by finishing the given hasher, which has been fed the request body,
+    /// and comparing the result to the claimed hash.
+    pub fn verify_hasher(self, hasher: PayloadHasher) -> Result<KeyMatch, ValidationError> {
+        let hash = hasher.finish().map_err(ValidationError::Hashing)?;
+        self.verify_hash(&hash)
+    }
+
+    /// Complete validation by comparing the given payload hash, calculated from the request
+    /// body, to the claimed hash.
+    pub fn verify_hash(self, hash: &PayloadHash) -> Result<KeyMatch, ValidationError> {
+        check_payload_hash(self.requirement, Some(hash), self.claimed_hash())?;
+        Ok(self.key_match)
+    }
+
+    /// Complete validation without a payload hash.  This fails if the payload hash is required.
+    pub fn verify_empty(self) -> Result<KeyMatch, ValidationError> {
+        check_payload_hash(self.requirement, None, self.claimed_hash())?;
+        Ok(self.key_match)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory_nonce_store() {
+        let store = MemoryNonceStore::new(Duration::from_secs(60));
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        assert!(store.check_and_insert("me", "abc", ts, ts));
+        assert!(!store.check_and_insert("me", "abc", ts, ts));
+        assert!(store.check_and_insert("you", "abc", ts, ts));
+        assert!(store.check_and_insert("me", "def", ts, ts));
+
+        // once the retention has passed, the nonce is forgotten
+        let later = ts + Duration::from_secs(61);
+        assert!(store.check_and_insert("me", "ghi", later, later));
+        assert!(store.check_and_insert("me", "abc", later, later));
+        assert_eq!(store.state.lock().unwrap().seen.len(), 2);
+    }
+
+    #[test]
+    fn test_memory_nonce_store_future_ts() {
+        let store = MemoryNonceStore::new(Duration::from_secs(60));
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        assert!(store.check_and_insert("me", "abc", now, now));
+
+        // a request with a timestamp in the future does not cause live nonces to be forgotten
+        let future = now + Duration::from_secs(120);
+        assert!(store.check_and_insert("me", "def", future, now));
+        assert!(!store.check_and_insert("me", "abc", now, now + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_memory_bewit_store() {
+        let store = MemoryBewitStore::new();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let exp = now + Duration::from_secs(60);
+        assert!(store.check_and_insert(b"mac1", exp, now));
+        assert!(!store.check_and_insert(b"mac1", exp, now));
+        assert!(store.check_and_insert(b"mac2", exp, now));
+
+        // once the bewit has expired, its MAC is forgotten
+        let later = exp + Duration::from_secs(1);
+        assert!(store.check_and_insert(b"mac3", later, later));
+        let state = store.state.lock().unwrap();
+        assert_eq!(state.used.len(), 1);
+        assert_eq!(state.expiry.len(), 1);
+    }
+
+    #[test]
+    fn test_memory_revocation_list() {
+        let list = MemoryRevocationList::new();
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        list.revoke("me", t(100), t(200));
+        assert!(list.is_revoked("me", t(100), t(50)));
+        assert!(list.is_revoked("me", t(200), t(50)));
+        assert!(!list.is_revoked("me", t(201), t(50)));
+        assert!(!list.is_revoked("you", t(150), t(50)));
+
+        // checking a revocation leaves the list unchanged
+        assert!(!list.is_revoked("me", t(300), t(250)));
+        assert_eq!(list.revoked.read().unwrap().len(), 1);
+
+        // once the window has passed, purging forgets the revocation
+        list.purge(t(200));
+        assert!(list.is_revoked("me", t(150), t(50)));
+        list.purge(t(201));
+        assert!(list.revoked.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_allowed_methods() {
+        let options = ValidationOptions::new();
+        assert!(options.common.method_allowed("DELETE"));
+        let options = options.allowed_methods(["GET", "HEAD"]);
+        assert!(options.common.method_allowed("GET"));
+        assert!(!options.common.method_allowed("get"));
+        assert!(!options.common.method_allowed("DELETE"));
+    }
+
+    #[test]
+    fn test_bewit_policy() {
+        let policy = BewitPolicy::new();
+        assert!(policy.common.method_allowed("GET"));
+        assert!(policy.common.method_allowed("HEAD"));
+        assert!(!policy.common.method_allowed("POST"));
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let policy = policy.max_ttl(Duration::from_secs(60));
+        assert!(policy
+            .check(now + Duration::from_secs(60), None, now)
+            .is_ok());
+        assert!(matches!(
+            policy.check(now + Duration::from_secs(61), None, now),
+            Err(ValidationError::TtlTooLong { ttl, max })
+                if ttl == Duration::from_secs(61) && max == Duration::from_secs(60)
+        ));
+
+        let policy = policy.ext(ExtRequirement::Required);
+        assert!(policy.check(now, Some("x"), now).is_ok());
+        assert!(matches!(
+            policy.check(now, None, now),
+            Err(ValidationError::MissingExt)
+        ));
+        let policy = policy.ext(ExtRequirement::Forbidden);
+        assert!(policy.check(now, None, now).is_ok());
+        assert!(matches!(
+            policy.check(now, Some("x"), now),
+            Err(ValidationError::UnexpectedExt)
+        ));
+    }
+}
@@ -0,0 +1,144 @@
+//! Statistical timing tests for the comparisons of secret values, in the style of dudect
+//! ("Dude, is my code constant time?", Reparaz et al., 2017).
+//!
+//! Each test times an operation on two classes of input: values equal to a secret, and values
+//! differing from it in the first byte.  A comparison that exits at the first differing byte is
+//! faster for the second class.  Welch's t-test is applied to the two sets of timings, and the
+//! test fails if the difference is significant.
+//!
+//! These tests take some time and are sensitive to a noisy machine, so they are ignored by
+//! default, and run in CI as a separate task.  Run them in release mode with
+//!
+//! ```text
+//! cargo test --release --test constant_time -- --ignored --test-threads 1
+//! ```
+//!
+//! Set `CONSTANT_TIME_SAMPLES` to change the number of samples.
+#![cfg(any(feature = "use_ring", feature = "use_openssl"))]
+
+use hawk::mac::Mac;
+use hawk::{PayloadHash, SHA256};
+use std::hint::black_box;
+use std::time::Instant;
+
+/// The number of timings to take for each class, unless `CONSTANT_TIME_SAMPLES` is set.
+const DEFAULT_SAMPLES: usize = 50_000;
+
+/// The number of operations in each timing, to amortize the cost of reading the clock.
+const BATCH: usize = 20;
+
+/// Timings above this percentile of all timings are discarded, as they are likely to have been
+/// interrupted.
+const CROP_PERCENTILE: f64 = 0.9;
+
+/// dudect considers |t| above 10 to be certain evidence of a timing leak, while leaky
+/// comparisons of short values typically show values well above 100.
+const T_THRESHOLD: f64 = 10.0;
+
+fn samples() -> usize {
+    match std::env::var("CONSTANT_TIME_SAMPLES") {
+        Ok(samples) => samples
+            .parse()
+            .expect("CONSTANT_TIME_SAMPLES must be a number"),
+        Err(_) => DEFAULT_SAMPLES,
+    }
+}
+
+/// A small xorshift generator, so that the order of the classes is unpredictable without
+/// depending on a random number generator crate.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_bool(&mut self) -> bool {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 & 1 == 1
+    }
+}
+
+/// Calculate Welch's t statistic for two samples.
+fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    fn mean_var(x: &[f64]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().sum::<f64>() / n;
+        let var = x.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0);
+        (mean, var)
+    }
+    let (mean_a, var_a) = mean_var(a);
+    let (mean_b, var_b) = mean_var(b);
+    (mean_a - mean_b) / (var_a / a.len() as f64 + var_b / b.len() as f64).sqrt()
+}
+
+/// Time `op` on both classes of input, `inputs[0]` being equal to the secret and `inputs[1]`
+/// differing from it, and return the t statistic for the difference between them.
+///
+/// Each batch operates on a fresh copy of the input, made before timing begins, so that the
+/// classes differ only in their contents and not in their location in memory.
+fn measure<T: Clone, F: FnMut(&T) -> bool>(inputs: [T; 2], mut op: F) -> f64 {
+    let samples = samples();
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    let mut timings = [Vec::with_capacity(samples), Vec::with_capacity(samples)];
+    while timings[0].len() < samples || timings[1].len() < samples {
+        let class = rng.next_bool() as usize;
+        let input = inputs[class].clone();
+        let start = Instant::now();
+        for _ in 0..BATCH {
+            black_box(op(black_box(&input)));
+        }
+        timings[class].push(start.elapsed().as_nanos() as f64);
+    }
+
+    let mut all: Vec<f64> = timings.iter().flatten().copied().collect();
+    all.sort_by(f64::total_cmp);
+    let cutoff = all[(all.len() as f64 * CROP_PERCENTILE) as usize];
+    let [equal, differing] =
+        timings.map(|t| t.into_iter().filter(|&v| v < cutoff).collect::<Vec<f64>>());
+    welch_t(&equal, &differing)
+}
+
+fn assert_constant_time<T: Clone, F: FnMut(&T) -> bool>(name: &str, inputs: [T; 2], op: F) {
+    let t = measure(inputs, op);
+    println!("{name}: t = {t:.2}");
+    assert!(
+        t.abs() < T_THRESHOLD,
+        "{name} appears not to be constant-time (t = {t:.2})"
+    );
+}
+
+/// Make a value equal to `secret`, and one differing from it in the first byte.
+fn inputs(secret: &[u8]) -> [Vec<u8>; 2] {
+    let mut differing = secret.to_vec();
+    differing[0] ^= 1;
+    [secret.to_vec(), differing]
+}
+
+#[test]
+#[ignore]
+fn payload_hash_eq() {
+    let secret = PayloadHash::new(SHA256, vec![0x5a; 32]).unwrap();
+    let inputs = inputs(secret.as_bytes()).map(|bytes| PayloadHash::new(SHA256, bytes).unwrap());
+    assert_constant_time("PayloadHash::eq", inputs, |candidate| &secret == candidate);
+}
+
+#[test]
+#[ignore]
+fn mac_eq() {
+    let secret = Mac::from(vec![0xa5; 32]);
+    let inputs = inputs(&secret).map(Mac::from);
+    assert_constant_time("Mac::eq", inputs, |candidate| &secret == candidate);
+}
+
+/// A check of the harness itself: an ordinary slice comparison of long values exits early, and
+/// should be detected.
+#[test]
+#[ignore]
+fn harness_detects_early_exit() {
+    let secret = vec![0x33u8; 4096];
+    let t = measure(inputs(&secret), |candidate| secret == *candidate);
+    println!("slice eq: t = {t:.2}");
+    assert!(
+        t.abs() > T_THRESHOLD,
+        "early exit was not detected (t = {t:.2})"
+    );
+}